@@ -0,0 +1,6 @@
+// Prints the JSON schemas of the contract's public types for client codegen:
+// cargo run --example export_schemas --features schema > schemas.json
+fn main() {
+    let schemas = contract::schema::type_schemas();
+    println!("{}", serde_json::to_string_pretty(&schemas).unwrap());
+}
@@ -0,0 +1,41 @@
+use crate::*;
+
+#[near(event_json(standard = "shade_agent"))]
+pub enum ContractEvent {
+    #[event_version("1.0.0")]
+    TcbStatusDegraded {
+        account_id: AccountId,
+        old_status: String,
+        new_status: String,
+    },
+    #[event_version("1.0.0")]
+    WorkerFlagged { account_id: AccountId },
+    #[event_version("1.0.0")]
+    WorkerUnflagged { account_id: AccountId },
+    #[event_version("1.0.0")]
+    WorkerUpdated {
+        account_id: AccountId,
+        changed_fields: Vec<String>,
+    },
+    #[event_version("1.0.0")]
+    PayoutRequested {
+        request_id: u32,
+        agent: AccountId,
+        recipient: AccountId,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    PayoutApproved { request_id: u32 },
+    #[event_version("1.0.0")]
+    PayoutRejected { request_id: u32 },
+    #[event_version("1.0.0")]
+    RegistrationReverted { account_id: AccountId },
+    #[event_version("1.0.0")]
+    PayoutCapped {
+        recipient: AccountId,
+        requested: NearToken,
+        paid: NearToken,
+    },
+    #[event_version("1.0.0")]
+    ReattestationForced { attestation_epoch: u64 },
+}
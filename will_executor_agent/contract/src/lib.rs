@@ -7,17 +7,117 @@ use near_sdk::{
     env::block_timestamp,
     log, near, require,
     store::{IterableMap, IterableSet},
-    AccountId, PanicOnDefault, Promise, NearToken
+    AccountId, CurveType, Gas, PanicOnDefault, Promise, PromiseOrValue, PublicKey, NearToken
 };
 
 
 mod collateral;
+mod events;
+mod liveness;
+mod payouts;
+mod quote;
+mod report_data;
+#[cfg(any(test, feature = "schema"))]
+pub mod schema;
+mod tcb;
+
+use events::ContractEvent;
+use liveness::Challenge;
+use payouts::{PayoutMode, PendingPayout};
+use quote::{detect_quote_type, QuoteType};
+use report_data::{decode_report_data, report_data_matches, ReportDataFormat};
+use tcb::{TcbDecision, TcbPolicy};
+
+const ABI_JSON: &str = include_str!("../abi.json");
+// quote verification and codehash replay have to finish in a single receipt
+const REGISTER_AGENT_MIN_GAS: Gas = Gas::from_tgas(200);
+const DEFAULT_CALLBACK_GAS: Gas = Gas::from_tgas(5);
 
 #[near(serializers = [json, borsh])]
 #[derive(Clone)]
 pub struct Worker {
     checksum: String,
     codehash: String,
+    allowed_recipients: Option<Vec<AccountId>>,
+    public_key: PublicKey,
+    last_seen: u64,
+    registered_at: u64,
+    role: Option<String>,
+    tcb_status: String,
+    advisory_ids: Vec<String>,
+    compromised: bool,
+    attestation_epoch: u64,
+}
+
+impl Worker {
+    fn allows_recipient(&self, account_id: &AccountId) -> bool {
+        self.allowed_recipients
+            .as_ref()
+            .is_none_or(|recipients| recipients.contains(account_id))
+    }
+
+    /// Names of the fields that differ from `other`, ignoring the volatile `last_seen` and the
+    /// `registered_at` that is kept from the first registration.
+    pub fn diff(&self, other: &Worker) -> WorkerDiff {
+        let mut changed_fields = vec![];
+        let mut check = |field: &str, changed: bool| {
+            if changed {
+                changed_fields.push(field.to_owned());
+            }
+        };
+
+        check("checksum", self.checksum != other.checksum);
+        check("codehash", self.codehash != other.codehash);
+        check(
+            "allowed_recipients",
+            self.allowed_recipients != other.allowed_recipients,
+        );
+        check("public_key", self.public_key != other.public_key);
+        check("role", self.role != other.role);
+        check("tcb_status", self.tcb_status != other.tcb_status);
+        check("advisory_ids", self.advisory_ids != other.advisory_ids);
+        check("compromised", self.compromised != other.compromised);
+        check(
+            "attestation_epoch",
+            self.attestation_epoch != other.attestation_epoch,
+        );
+
+        WorkerDiff { changed_fields }
+    }
+}
+
+impl PartialEq for Worker {
+    fn eq(&self, other: &Self) -> bool {
+        self.diff(other).is_empty()
+    }
+}
+
+pub struct WorkerDiff {
+    pub changed_fields: Vec<String>,
+}
+
+impl WorkerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed_fields.is_empty()
+    }
+}
+
+/// Outcome of every `pay_by_agent` guard for a hypothetical payout.
+#[near(serializers = [json])]
+pub struct PayoutSimulation {
+    pub recipient: AccountId,
+    pub amount: NearToken,
+    pub owner_bypass: bool,
+    pub agent_registered: bool,
+    pub codehash_approved: bool,
+    pub attestation_current: bool,
+    pub not_compromised: bool,
+    pub role_allowed: bool,
+    pub below_approval_threshold: bool,
+    pub not_paying_self: bool,
+    pub recipient_allowed: bool,
+    pub sufficient_balance: bool,
+    pub would_succeed: bool,
 }
 
 #[near(contract_state)]
@@ -26,6 +126,27 @@ pub struct Contract {
     pub owner_id: AccountId,
     pub approved_codehashes: IterableSet<String>,
     pub worker_by_account_id: IterableMap<AccountId, Worker>,
+    pub agent_transfer_to_self_forbidden: bool,
+    pub report_data_format: ReportDataFormat,
+    pub challenge_by_account_id: IterableMap<AccountId, Challenge>,
+    pub max_tcb_info_bytes: u64,
+    pub required_role_by_method: IterableMap<String, String>,
+    pub advisory_counts: IterableMap<String, u64>,
+    pub payout_approval_threshold: Option<NearToken>,
+    pub pending_payouts: IterableMap<u32, PendingPayout>,
+    pub current_payout_request_id: u32,
+    pub banned_codehashes: IterableSet<String>,
+    pub registration_whitelist: Option<IterableSet<AccountId>>,
+    pub max_approved_codehashes: u32,
+    pub owner_bypass_enabled: bool,
+    pub registration_undo_window_seconds: u64,
+    pub codehash_rtmr_index: u8,
+    pub payout_mode: PayoutMode,
+    pub callback_gas: Gas,
+    pub attestation_epoch: u64,
+    pub report_data_format_by_account_id: IterableMap<AccountId, ReportDataFormat>,
+    pub reverted_registrations: IterableSet<AccountId>,
+    pub tcb_policy: TcbPolicy,
 }
 
 #[near]
@@ -37,14 +158,201 @@ impl Contract {
             owner_id,
             approved_codehashes: IterableSet::new(b"a"),
             worker_by_account_id: IterableMap::new(b"b"),
+            agent_transfer_to_self_forbidden: true,
+            report_data_format: ReportDataFormat::Utf8,
+            challenge_by_account_id: IterableMap::new(b"c"),
+            max_tcb_info_bytes: 64 * 1024,
+            required_role_by_method: IterableMap::new(b"d"),
+            advisory_counts: IterableMap::new(b"e"),
+            payout_approval_threshold: None,
+            pending_payouts: IterableMap::new(b"f"),
+            current_payout_request_id: 0,
+            banned_codehashes: IterableSet::new(b"g"),
+            registration_whitelist: None,
+            max_approved_codehashes: 100,
+            owner_bypass_enabled: true,
+            registration_undo_window_seconds: 10 * 60,
+            codehash_rtmr_index: 3,
+            payout_mode: PayoutMode::Strict,
+            callback_gas: DEFAULT_CALLBACK_GAS,
+            attestation_epoch: 0,
+            report_data_format_by_account_id: IterableMap::new(b"i"),
+            reverted_registrations: IterableSet::new(b"j"),
+            tcb_policy: TcbPolicy::default(),
         }
     }
 
-    pub fn approve_codehash(&mut self, codehash: String) {
+    /// Returns `true` if the codehash was not approved before.
+    pub fn approve_codehash(&mut self, codehash: String) -> bool {
+        self.require_owner();
+        require!(
+            !self.banned_codehashes.contains(&codehash),
+            format!("codehash is banned: {}", codehash)
+        );
+        require!(
+            self.approved_codehashes.contains(&codehash)
+                || self.approved_codehashes.len() < self.max_approved_codehashes,
+            format!(
+                "cannot approve more than {} codehashes",
+                self.max_approved_codehashes
+            )
+        );
+        self.approved_codehashes.insert(codehash)
+    }
+
+    /// Returns `true` if the codehash was approved before.
+    pub fn remove_codehash(&mut self, codehash: String) -> bool {
         self.require_owner();
-        self.approved_codehashes.insert(codehash);
+        self.approved_codehashes.remove(&codehash)
+    }
+
+    /// Lowering the cap below the current count only blocks new approvals, nothing is revoked.
+    pub fn set_max_approved_codehashes(&mut self, max_approved_codehashes: u32) {
+        self.require_owner();
+        self.max_approved_codehashes = max_approved_codehashes;
+    }
+
+    /// View method: the number of approved codehashes and the cap on them.
+    pub fn get_approved_codehash_capacity(&self) -> (u32, u32) {
+        (self.approved_codehashes.len(), self.max_approved_codehashes)
+    }
+
+    /// Bans a codehash so it can't be approved again, revoking it if it is currently approved.
+    /// Returns `true` if the codehash was not banned before.
+    pub fn ban_codehash(&mut self, codehash: String) -> bool {
+        self.require_owner();
+        self.approved_codehashes.remove(&codehash);
+        self.banned_codehashes.insert(codehash)
+    }
+
+    /// Returns `true` if the codehash was banned before.
+    pub fn unban_codehash(&mut self, codehash: String) -> bool {
+        self.require_owner();
+        self.banned_codehashes.remove(&codehash)
+    }
+
+    pub fn get_banned_codehashes(&self) -> Vec<String> {
+        self.banned_codehashes.iter().cloned().collect()
+    }
+
+    /// Restricts `register_agent` to whitelisted accounts, starting from an empty whitelist.
+    pub fn enable_registration_whitelist(&mut self) {
+        self.require_owner();
+        if self.registration_whitelist.is_none() {
+            self.registration_whitelist = Some(IterableSet::new(b"h"));
+        }
+    }
+
+    /// Opens registration to every account again and drops the whitelist.
+    pub fn disable_registration_whitelist(&mut self) {
+        self.require_owner();
+        if let Some(mut whitelist) = self.registration_whitelist.take() {
+            whitelist.clear();
+        }
+    }
+
+    /// Returns `true` if the account was not whitelisted before.
+    pub fn add_to_registration_whitelist(&mut self, account_id: AccountId) -> bool {
+        self.require_owner();
+        self.registration_whitelist
+            .as_mut()
+            .expect("registration whitelist is not enabled")
+            .insert(account_id)
+    }
+
+    /// Returns `true` if the account was whitelisted before.
+    pub fn remove_from_registration_whitelist(&mut self, account_id: AccountId) -> bool {
+        self.require_owner();
+        self.registration_whitelist
+            .as_mut()
+            .expect("registration whitelist is not enabled")
+            .remove(&account_id)
+    }
+
+    /// `None` while registration is open to every account.
+    pub fn get_registration_whitelist(&self) -> Option<Vec<AccountId>> {
+        self.registration_whitelist
+            .as_ref()
+            .map(|whitelist| whitelist.iter().cloned().collect())
+    }
+
+    pub fn is_registration_allowed(&self, account_id: AccountId) -> bool {
+        self.registration_whitelist
+            .as_ref()
+            .is_none_or(|whitelist| whitelist.contains(&account_id))
     }
     
+    pub fn set_agent_transfer_to_self_forbidden(&mut self, forbidden: bool) {
+        self.require_owner();
+        self.agent_transfer_to_self_forbidden = forbidden;
+    }
+
+    /// Lets the owner call `pay_by_agent` without a registered agent for manual intervention.
+    pub fn set_owner_bypass_enabled(&mut self, enabled: bool) {
+        self.require_owner();
+        self.owner_bypass_enabled = enabled;
+    }
+
+    /// Static gas attached to callbacks on this contract, e.g. `on_payout_complete`.
+    pub fn set_callback_gas(&mut self, gas: Gas) {
+        self.require_owner();
+        self.callback_gas = gas;
+    }
+
+    pub fn get_callback_gas(&self) -> Gas {
+        self.callback_gas
+    }
+
+    pub fn set_report_data_format(&mut self, format: ReportDataFormat) {
+        self.require_owner();
+        self.report_data_format = format;
+    }
+
+    pub fn get_report_data_format(&self) -> ReportDataFormat {
+        self.report_data_format
+    }
+
+    /// Decodes the agent's `report_data` in `format` instead of the contract-wide format, `None`
+    /// goes back to the contract-wide one. Only the owner can pick the format, a caller-chosen
+    /// one could read a different account out of someone else's quote.
+    pub fn set_agent_report_data_format(
+        &mut self,
+        account_id: AccountId,
+        format: Option<ReportDataFormat>,
+    ) {
+        self.require_owner();
+        match format {
+            Some(format) => {
+                self.report_data_format_by_account_id.insert(account_id, format);
+            }
+            None => {
+                self.report_data_format_by_account_id.remove(&account_id);
+            }
+        }
+    }
+
+    pub fn get_agent_report_data_format(&self, account_id: AccountId) -> ReportDataFormat {
+        self.report_data_format_for(&account_id)
+    }
+
+    pub fn set_tcb_policy(&mut self, policy: TcbPolicy) {
+        self.require_owner();
+        require!(
+            tcb::is_known_tcb_status(&policy.max_tcb_status),
+            format!("unknown tcb status: {}", policy.max_tcb_status)
+        );
+        self.tcb_policy = policy;
+    }
+
+    pub fn get_tcb_policy(&self) -> TcbPolicy {
+        self.tcb_policy.clone()
+    }
+
+    pub fn set_max_tcb_info_bytes(&mut self, max_tcb_info_bytes: u64) {
+        self.require_owner();
+        self.max_tcb_info_bytes = max_tcb_info_bytes;
+    }
+
     pub fn register_agent(
         &mut self,
         quote_hex: String,
@@ -52,39 +360,143 @@ impl Contract {
         checksum: String,
         tcb_info: String,
     ) -> bool {
+        require!(
+            env::prepaid_gas() >= REGISTER_AGENT_MIN_GAS,
+            format!(
+                "register_agent needs at least {} Tgas",
+                REGISTER_AGENT_MIN_GAS.as_tgas()
+            )
+        );
+        require!(
+            self.is_registration_allowed(env::predecessor_account_id()),
+            "account is not whitelisted for registration"
+        );
+        // Reject oversized input before spending gas on parsing it
+        require!(
+            tcb_info.len() as u64 <= self.max_tcb_info_bytes,
+            format!(
+                "tcb_info needs to be at most {} bytes",
+                self.max_tcb_info_bytes
+            )
+        );
+
         let collateral = collateral::get_collateral(collateral);
         let quote = decode(quote_hex).unwrap();
+        require!(
+            detect_quote_type(&quote) == Some(QuoteType::Tdx),
+            "only TDX quotes can register agents"
+        );
         let now = block_timestamp() / 1000000000;
         let result = verify::verify(&quote, &collateral, now).expect("report is not verified");
         let report = result.report.as_td10().unwrap();
-        let report_data = format!("{}", String::from_utf8_lossy(&report.report_data));
+        let (report_data, rtmrs) = attested_fields(
+            report.report_data.to_vec(),
+            [report.rt_mr0, report.rt_mr1, report.rt_mr2, report.rt_mr3],
+        );
 
         // Verify the predecessor matches the report data
+        let predecessor = env::predecessor_account_id();
+        let report_data_format = self.report_data_format_for(&predecessor);
+        let decoded_report_data = decode_report_data(report_data_format, &report_data);
         require!(
-            env::predecessor_account_id() == report_data,
-            format!("predecessor_account_id != report_data: {}", report_data)
+            self.report_data_binds(&predecessor, &report_data),
+            format!("predecessor_account_id != report_data: {}", decoded_report_data)
         );
 
-        let rtmr3 = encode(report.rt_mr3.to_vec());
+        let rtmr = collateral::select_rtmr(&rtmrs, self.codehash_rtmr_index);
         let (shade_agent_api_image, shade_agent_app_image) =
-            collateral::verify_codehash(tcb_info, rtmr3);
+            collateral::verify_codehash(tcb_info, self.codehash_rtmr_index, rtmr);
 
         // Verify the code hashes are approved
         require!(self.approved_codehashes.contains(&shade_agent_api_image));
         require!(self.approved_codehashes.contains(&shade_agent_app_image));
 
-        let predecessor = env::predecessor_account_id();
-        self.worker_by_account_id.insert(
+        self.save_worker(
             predecessor,
             Worker {
                 checksum,
                 codehash: shade_agent_app_image,
+                allowed_recipients: None,
+                public_key: env::signer_account_pk(),
+                last_seen: now,
+                registered_at: now,
+                role: None,
+                tcb_status: result.status.clone(),
+                advisory_ids: result.advisory_ids.clone(),
+                compromised: false,
+                attestation_epoch: self.attestation_epoch,
             },
         );
 
         true
     }
 
+    /// Owner-only bootstrap helper for dev deployments: approves the codehashes and registers the
+    /// owner account as an agent in the same transaction. Only works if the quote's `report_data`
+    /// binds the owner account itself, any failed check reverts the approvals as well.
+    pub fn approve_and_register(
+        &mut self,
+        codehashes: Vec<String>,
+        quote_hex: String,
+        collateral: String,
+        checksum: String,
+        tcb_info: String,
+    ) -> bool {
+        self.require_owner();
+        for codehash in codehashes {
+            self.approve_codehash(codehash);
+        }
+        self.register_agent(quote_hex, collateral, checksum, tcb_info)
+    }
+
+    /// Selects the RTMR the app compose hash is measured into, 3 for current dstack builds.
+    pub fn set_codehash_rtmr_index(&mut self, index: u8) {
+        self.require_owner();
+        require!(index <= 3, "rtmr index needs to be between 0 and 3");
+        self.codehash_rtmr_index = index;
+    }
+
+    pub fn get_codehash_rtmr_index(&self) -> u8 {
+        self.codehash_rtmr_index
+    }
+
+    pub fn set_registration_undo_window_seconds(&mut self, seconds: u64) {
+        self.require_owner();
+        self.registration_undo_window_seconds = seconds;
+    }
+
+    /// Lets a freshly registered agent remove its worker again, e.g. after registering with the
+    /// wrong checksum. Registration charges no fee, so there is nothing to refund. Each account
+    /// can revert once, so register/revert cycles can't keep the window open, and a flagged
+    /// worker can't revert its way out of the flag.
+    pub fn revert_registration(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let worker = self.get_agent(account_id.clone());
+        require!(!worker.compromised, "worker is flagged as compromised");
+        require!(
+            !self.reverted_registrations.contains(&account_id),
+            "registration was already reverted once"
+        );
+        let now = block_timestamp() / 1000000000;
+        require!(
+            now.saturating_sub(worker.registered_at) <= self.registration_undo_window_seconds,
+            "registration undo window has passed"
+        );
+
+        self.update_advisory_counts(&worker.advisory_ids, &[]);
+        self.worker_by_account_id.remove(&account_id);
+        self.challenge_by_account_id.remove(&account_id);
+        self.reverted_registrations.insert(account_id.clone());
+
+        ContractEvent::RegistrationReverted { account_id }.emit();
+    }
+
+    /// View method: runs quote verification and checks the quote against the owner's
+    /// `TcbPolicy`, without registering anything. `now` is in seconds.
+    pub fn would_accept_tcb(&self, quote_hex: String, collateral: String, now: u64) -> TcbDecision {
+        tcb::evaluate_quote(&self.tcb_policy, quote_hex, collateral, now)
+    }
+
     pub fn get_agent(&self, account_id: AccountId) -> Worker {
         self.worker_by_account_id
             .get(&account_id)
@@ -92,22 +504,373 @@ impl Contract {
             .to_owned()
     }
 
+    /// View method: lets a reproducible-build CI check that its hash is approved.
+    pub fn matches_approved_codehash(&self, claimed_codehash: String) -> bool {
+        self.approved_codehashes.contains(&claimed_codehash)
+    }
+
+    /// View method: `false` for unregistered accounts.
+    pub fn matches_worker(&self, account_id: AccountId, claimed_codehash: String) -> bool {
+        self.worker_by_account_id
+            .get(&account_id)
+            .is_some_and(|worker| worker.codehash == claimed_codehash)
+    }
+
+    /// View method: resolves several workers at once, `None` for unregistered accounts.
+    pub fn get_agents(&self, account_ids: Vec<AccountId>) -> Vec<(AccountId, Option<Worker>)> {
+        require!(
+            account_ids.len() <= 100,
+            "Account ids need to be at most 100 accounts"
+        );
+
+        account_ids
+            .into_iter()
+            .map(|account_id| {
+                let worker = self.worker_by_account_id.get(&account_id).cloned();
+                (account_id, worker)
+            })
+            .collect()
+    }
+
+    /// View method: advisory ids present across all workers with the number of affected workers,
+    /// most widespread first.
+    pub fn fleet_advisories(&self, limit: u64) -> Vec<(String, u64)> {
+        let mut advisories: Vec<(String, u64)> = self
+            .advisory_counts
+            .iter()
+            .map(|(advisory_id, count)| (advisory_id.clone(), *count))
+            .collect();
+        advisories.sort_by(|(a_id, a_count), (b_id, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_id.cmp(b_id))
+        });
+        advisories.truncate(limit as usize);
+        advisories
+    }
+
+    pub fn set_agent_allowed_recipients(&mut self, agent: AccountId, recipients: Vec<AccountId>) {
+        self.require_owner();
+        require!(
+            recipients.len() <= 100,
+            "Allowed recipients need to be at most 100 accounts"
+        );
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&agent)
+            .expect("no worker found");
+        worker.allowed_recipients = Some(recipients);
+    }
+
+    pub fn clear_agent_allowed_recipients(&mut self, agent: AccountId) {
+        self.require_owner();
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&agent)
+            .expect("no worker found");
+        worker.allowed_recipients = None;
+    }
+
+    pub fn set_agent_role(&mut self, agent: AccountId, role: Option<String>) {
+        self.require_owner();
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&agent)
+            .expect("no worker found");
+        worker.role = role;
+    }
+
+    /// Sets the role a worker needs to call `method`, `None` lifts the requirement.
+    pub fn set_required_role(&mut self, method: String, role: Option<String>) {
+        self.require_owner();
+        match role {
+            Some(role) => self.required_role_by_method.insert(method, role),
+            None => self.required_role_by_method.remove(&method),
+        };
+    }
+
+    pub fn get_required_role(&self, method: String) -> Option<String> {
+        self.required_role_by_method.get(&method).cloned()
+    }
+
+    pub fn flag_worker_compromised(&mut self, account_id: AccountId) {
+        self.require_owner();
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&account_id)
+            .expect("no worker found");
+        worker.compromised = true;
+
+        ContractEvent::WorkerFlagged { account_id }.emit();
+    }
+
+    pub fn unflag_worker(&mut self, account_id: AccountId) {
+        self.require_owner();
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&account_id)
+            .expect("no worker found");
+        worker.compromised = false;
+
+        ContractEvent::WorkerUnflagged { account_id }.emit();
+    }
+
+    /// Expires every current attestation, workers are rejected until they call `register_agent`
+    /// again. Returns the new epoch.
+    pub fn force_reattest_all(&mut self) -> u64 {
+        self.require_owner();
+        self.attestation_epoch += 1;
+
+        ContractEvent::ReattestationForced {
+            attestation_epoch: self.attestation_epoch,
+        }
+        .emit();
+        self.attestation_epoch
+    }
+
+    pub fn get_attestation_epoch(&self) -> u64 {
+        self.attestation_epoch
+    }
+
+    // Stores a freshly attested worker, carrying over settings from a previous registration
+    fn save_worker(&mut self, account_id: AccountId, mut worker: Worker) {
+        worker.advisory_ids.sort();
+        worker.advisory_ids.dedup();
+
+        let mut previous_advisory_ids = vec![];
+        if let Some(previous) = self.worker_by_account_id.get(&account_id) {
+            previous_advisory_ids = previous.advisory_ids.clone();
+
+            // keep owner-configured settings across re-registrations, a compromised
+            // worker stays flagged until the owner clears it
+            worker.allowed_recipients = previous.allowed_recipients.clone();
+            worker.role = previous.role.clone();
+            worker.compromised = previous.compromised;
+            worker.registered_at = previous.registered_at;
+
+            if tcb::is_tcb_degraded(&previous.tcb_status, &worker.tcb_status) {
+                ContractEvent::TcbStatusDegraded {
+                    account_id: account_id.clone(),
+                    old_status: previous.tcb_status.clone(),
+                    new_status: worker.tcb_status.clone(),
+                }
+                .emit();
+            }
+
+            let diff = previous.diff(&worker);
+            if !diff.is_empty() {
+                ContractEvent::WorkerUpdated {
+                    account_id: account_id.clone(),
+                    changed_fields: diff.changed_fields,
+                }
+                .emit();
+            }
+        }
+
+        self.update_advisory_counts(&previous_advisory_ids, &worker.advisory_ids);
+        self.worker_by_account_id.insert(account_id, worker);
+    }
+
+    fn update_advisory_counts(&mut self, removed: &[String], added: &[String]) {
+        for advisory_id in removed {
+            if let Some(count) = self.advisory_counts.get_mut(advisory_id) {
+                *count -= 1;
+                if *count == 0 {
+                    self.advisory_counts.remove(advisory_id);
+                }
+            }
+        }
+        for advisory_id in added {
+            let count = self.advisory_counts.get(advisory_id).copied().unwrap_or(0);
+            self.advisory_counts.insert(advisory_id.clone(), count + 1);
+        }
+    }
+
     fn require_owner(&mut self) {
         require!(env::predecessor_account_id() == self.owner_id);
     }
 
     fn require_approved_codehash(&mut self) {
-        let worker = self.get_agent(env::predecessor_account_id());
+        self.require_approved_worker(&env::predecessor_account_id());
+    }
+
+    fn require_approved_worker(&self, account_id: &AccountId) {
+        let worker = self.get_agent(account_id.clone());
         require!(
             self.approved_codehashes.contains(&worker.codehash),
             format!("codehash not approved: {}", worker.codehash)
         );
+        require!(!worker.compromised, "worker is flagged as compromised");
+        require!(
+            worker.attestation_epoch >= self.attestation_epoch,
+            "worker needs to re-attest"
+        );
     }
 
-    pub fn pay_by_agent(&mut self, account_id: AccountId, amount: NearToken) {
-        // self.require_approved_codehash();
+    // Every `.then()` back into this contract goes through here so it gets the configured gas
+    fn callback(&self) -> ContractExt {
+        Self::ext(env::current_account_id()).with_static_gas(self.callback_gas)
+    }
+
+    fn report_data_format_for(&self, account_id: &AccountId) -> ReportDataFormat {
+        self.report_data_format_by_account_id
+            .get(account_id)
+            .copied()
+            .unwrap_or(self.report_data_format)
+    }
+
+    fn report_data_binds(&self, account_id: &AccountId, report_data: &[u8]) -> bool {
+        report_data_matches(
+            self.report_data_format_for(account_id),
+            report_data,
+            account_id.as_str(),
+        )
+    }
+
+    fn is_owner_bypass(&self, account_id: &AccountId) -> bool {
+        self.owner_bypass_enabled && *account_id == self.owner_id
+    }
+
+    fn has_required_role(&self, method: &str, account_id: &AccountId) -> bool {
+        match self.required_role_by_method.get(method) {
+            None => true,
+            Some(role) => self
+                .worker_by_account_id
+                .get(account_id)
+                .is_some_and(|worker| worker.role.as_ref() == Some(role)),
+        }
+    }
+
+    fn require_role(&self, method: &str) {
+        require!(
+            self.has_required_role(method, &env::predecessor_account_id()),
+            format!("agent lacks the role required for {}", method)
+        );
+    }
+
+    /// Returns the transfer chained with `on_payout_complete`, which resolves to whether it succeeded.
+    pub fn pay_by_agent(
+        &mut self,
+        account_id: AccountId,
+        amount: NearToken,
+    ) -> PromiseOrValue<bool> {
+        // the owner bypass is for manual payouts without a registered agent, so it skips the
+        // checks that need a worker record
+        if !self.is_owner_bypass(&env::predecessor_account_id()) {
+            self.require_approved_codehash();
+            self.require_role("pay_by_agent");
+        }
+        require!(
+            !self.agent_transfer_to_self_forbidden
+                || account_id != env::predecessor_account_id(),
+            "agent cannot pay itself"
+        );
+        if let Some(worker) = self
+            .worker_by_account_id
+            .get(&env::predecessor_account_id())
+        {
+            require!(!worker.compromised, "worker is flagged as compromised");
+            require!(
+                worker.allows_recipient(&account_id),
+                format!("recipient not allowed for agent: {}", account_id)
+            );
+        }
+        require!(
+            !self.requires_approval(amount),
+            "payout requires owner approval, use request_payout"
+        );
+        let amount = self.payable_amount(&account_id, amount);
         log!("Paying {:?} yoctoNEAR to {:?}", amount, account_id);
-        Promise::new(account_id).transfer(amount);
+        self.transfer_with_callback(account_id, amount)
+    }
+
+    /// View method: evaluates all payout guards at once instead of stopping at the first failure.
+    pub fn simulate_pay_by_agent(
+        &self,
+        agent: AccountId,
+        account_id: AccountId,
+        amount: NearToken,
+    ) -> PayoutSimulation {
+        let worker = self.worker_by_account_id.get(&agent);
+        let owner_bypass = self.is_owner_bypass(&agent);
+        let agent_registered = worker.is_some();
+        let codehash_approved =
+            worker.is_some_and(|worker| self.approved_codehashes.contains(&worker.codehash));
+        let attestation_current =
+            worker.is_some_and(|worker| worker.attestation_epoch >= self.attestation_epoch);
+        let not_compromised = worker.is_none_or(|worker| !worker.compromised);
+        let role_allowed = owner_bypass || self.has_required_role("pay_by_agent", &agent);
+        let below_approval_threshold = !self.requires_approval(amount);
+        let not_paying_self = !self.agent_transfer_to_self_forbidden || account_id != agent;
+        let recipient_allowed = worker.is_none_or(|worker| worker.allows_recipient(&account_id));
+        let distributable = self.distributable_balance();
+        let sufficient_balance = amount <= distributable;
+
+        PayoutSimulation {
+            recipient: account_id,
+            amount,
+            owner_bypass,
+            agent_registered,
+            codehash_approved,
+            attestation_current,
+            not_compromised,
+            role_allowed,
+            below_approval_threshold,
+            not_paying_self,
+            recipient_allowed,
+            sufficient_balance,
+            would_succeed: (owner_bypass
+                || (agent_registered && codehash_approved && attestation_current))
+                && not_compromised
+                && role_allowed
+                && below_approval_threshold
+                && not_paying_self
+                && recipient_allowed
+                && (sufficient_balance
+                    || (self.payout_mode == PayoutMode::BestEffort && !distributable.is_zero())),
+        }
+    }
+
+    /// View method: checks that a string is a valid account id before it is used as an argument.
+    pub fn validate_account_id(&self, s: String) -> bool {
+        AccountId::try_from(s).is_ok()
+    }
+
+    /// View method: the largest payout the agent could make right now, zero if it is not authorized.
+    pub fn max_payable_by_agent(&self, account_id: AccountId) -> NearToken {
+        let worker = self.worker_by_account_id.get(&account_id);
+        let authorized = (self.is_owner_bypass(&account_id)
+            || (worker.is_some_and(|worker| {
+                self.approved_codehashes.contains(&worker.codehash)
+                    && worker.attestation_epoch >= self.attestation_epoch
+            }) && self.has_required_role("pay_by_agent", &account_id)))
+            && worker.is_none_or(|worker| !worker.compromised);
+        if !authorized {
+            return NearToken::from_yoctonear(0);
+        }
+
+        let distributable = self.distributable_balance();
+        match self.payout_approval_threshold {
+            // anything from the threshold up has to go through request_payout
+            Some(threshold) => {
+                distributable.min(threshold.saturating_sub(NearToken::from_yoctonear(1)))
+            }
+            None => distributable,
+        }
+    }
+
+    fn requires_approval(&self, amount: NearToken) -> bool {
+        self.payout_approval_threshold
+            .is_some_and(|threshold| amount >= threshold)
+    }
+
+    // Vault balance minus what the account has to keep locked for storage staking
+    fn distributable_balance(&self) -> NearToken {
+        let storage_reserve = env::storage_byte_cost().saturating_mul(env::storage_usage().into());
+        env::account_balance().saturating_sub(storage_reserve)
+    }
+
+    /// View method: vault balance that can be paid out without touching the storage reserve.
+    pub fn get_distributable_balance(&self) -> NearToken {
+        self.distributable_balance()
     }
 
     pub fn get_vault_balance(&self) -> NearToken {
@@ -115,4 +878,986 @@ impl Contract {
         env::account_balance()
     }
 
+    /// View method: returns a JSON description of the public methods and their types.
+    pub fn get_contract_abi(&self) -> serde_json::Value {
+        serde_json::from_str(ABI_JSON).expect("abi.json should be valid JSON")
+    }
+
+}
+
+#[cfg(test)]
+thread_local! {
+    static TEST_ATTESTED_FIELDS: std::cell::RefCell<Option<(Vec<u8>, [[u8; 48]; 4])>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// `report_data` and RTMRs of a verified report. Unit tests can substitute them, since no sample
+/// quote binds a test account or measures a shade agent app_compose.
+fn attested_fields(report_data: Vec<u8>, rtmrs: [[u8; 48]; 4]) -> (Vec<u8>, [[u8; 48]; 4]) {
+    #[cfg(test)]
+    if let Some(fields) = TEST_ATTESTED_FIELDS.with(|fields| fields.borrow().clone()) {
+        return fields;
+    }
+    (report_data, rtmrs)
+}
+
+#[cfg(test)]
+fn setup_contract() -> Contract {
+    use near_sdk::test_utils::accounts;
+
+    set_context(accounts(0), 0);
+    Contract::init(accounts(0))
+}
+
+// accounts(1) registered as a worker on the approved "codehash"
+#[cfg(test)]
+fn setup_registered_agent() -> Contract {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract
+        .worker_by_account_id
+        .insert(accounts(1), test_worker("codehash"));
+    contract.approve_codehash(String::from("codehash"));
+    contract
+}
+
+#[cfg(test)]
+fn test_worker(codehash: &str) -> Worker {
+    Worker {
+        checksum: String::from("checksum"),
+        codehash: codehash.to_owned(),
+        allowed_recipients: None,
+        public_key: PublicKey::from_parts(
+            CurveType::ED25519,
+            test_signing_key().verifying_key().to_bytes().to_vec(),
+        )
+        .unwrap(),
+        last_seen: 0,
+        registered_at: 0,
+        role: None,
+        tcb_status: String::from("UpToDate"),
+        advisory_ids: vec![],
+        compromised: false,
+        attestation_epoch: 0,
+    }
+}
+
+#[cfg(test)]
+fn test_signing_key() -> ed25519_dalek::SigningKey {
+    ed25519_dalek::SigningKey::from_bytes(&[1u8; 32])
+}
+
+#[cfg(test)]
+fn set_context(predecessor: AccountId, now: u64) {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor)
+        .block_timestamp(now * 1_000_000_000)
+        .build());
+}
+
+#[test]
+fn test_simulate_pay_by_agent_reports_all_failures() {
+    use near_sdk::test_utils::accounts;
+
+    let contract = setup_contract();
+    let too_much = NearToken::from_yoctonear(env::account_balance().as_yoctonear() + 1);
+
+    // unregistered agent asking for more than the vault holds fails every guard
+    let simulation = contract.simulate_pay_by_agent(accounts(1), accounts(2), too_much);
+    assert!(!simulation.agent_registered);
+    assert!(!simulation.codehash_approved);
+    assert!(!simulation.sufficient_balance);
+    assert!(!simulation.would_succeed);
+}
+
+#[test]
+fn test_simulate_pay_by_agent_codehash_not_approved() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract
+        .worker_by_account_id
+        .insert(accounts(1), test_worker("codehash"));
+    let amount = NearToken::from_yoctonear(1);
+
+    let simulation = contract.simulate_pay_by_agent(accounts(1), accounts(2), amount);
+    assert!(simulation.agent_registered);
+    assert!(!simulation.codehash_approved);
+    assert!(simulation.sufficient_balance);
+    assert!(!simulation.would_succeed);
+
+    contract.approve_codehash(String::from("codehash"));
+    let simulation = contract.simulate_pay_by_agent(accounts(1), accounts(2), amount);
+    assert!(simulation.would_succeed);
+}
+
+#[test]
+fn test_validate_account_id() {
+    let contract = setup_contract();
+
+    assert!(contract.validate_account_id(String::from("ajitesh-2.testnet")));
+    assert!(!contract.validate_account_id(String::from("Ajitesh 2.testnet")));
+    assert!(!contract.validate_account_id(String::new()));
+}
+
+#[test]
+#[should_panic(expected = "agent cannot pay itself")]
+fn test_pay_by_agent_rejects_self_payment() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.pay_by_agent(accounts(0), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_pay_by_agent_self_payment_allowed_when_disabled() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.set_agent_transfer_to_self_forbidden(false);
+    contract.pay_by_agent(accounts(0), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_agent_allowed_recipients() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.set_agent_allowed_recipients(accounts(1), vec![accounts(2)]);
+    assert_eq!(
+        contract.get_agent(accounts(1)).allowed_recipients,
+        Some(vec![accounts(2)])
+    );
+
+    let amount = NearToken::from_yoctonear(1);
+    assert!(
+        contract
+            .simulate_pay_by_agent(accounts(1), accounts(2), amount)
+            .recipient_allowed
+    );
+    assert!(
+        !contract
+            .simulate_pay_by_agent(accounts(1), accounts(3), amount)
+            .recipient_allowed
+    );
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), amount);
+
+    set_context(accounts(0), 0);
+    contract.clear_agent_allowed_recipients(accounts(1));
+    assert!(contract.get_agent(accounts(1)).allowed_recipients.is_none());
+}
+
+#[test]
+#[should_panic(expected = "recipient not allowed for agent")]
+fn test_pay_by_agent_rejects_recipient_outside_whitelist() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.set_agent_allowed_recipients(accounts(1), vec![accounts(2)]);
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(3), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_get_agents_mixed_registration() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract
+        .worker_by_account_id
+        .insert(accounts(2), test_worker("codehash"));
+
+    let agents = contract.get_agents(vec![accounts(1), accounts(2), accounts(3)]);
+    let account_ids: Vec<AccountId> = agents.iter().map(|(id, _)| id.clone()).collect();
+    assert_eq!(account_ids, vec![accounts(1), accounts(2), accounts(3)]);
+    assert!(agents[0].1.is_none());
+    assert_eq!(agents[1].1.as_ref().unwrap().codehash, "codehash");
+    assert!(agents[2].1.is_none());
+}
+
+#[test]
+fn test_approve_and_remove_codehash_report_changes() {
+    let mut contract = setup_contract();
+
+    assert!(contract.approve_codehash(String::from("codehash")));
+    assert!(!contract.approve_codehash(String::from("codehash")));
+
+    assert!(contract.remove_codehash(String::from("codehash")));
+    assert!(!contract.remove_codehash(String::from("codehash")));
+}
+
+#[test]
+#[should_panic(expected = "tcb_info needs to be at most 16 bytes")]
+fn test_register_agent_rejects_oversized_tcb_info() {
+    let mut contract = setup_contract();
+    contract.set_max_tcb_info_bytes(16);
+
+    contract.register_agent(
+        String::new(),
+        String::new(),
+        String::from("checksum"),
+        "x".repeat(17),
+    );
+}
+
+#[test]
+#[should_panic(expected = "agent lacks the role required for pay_by_agent")]
+fn test_pay_by_agent_requires_role() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.set_required_role(String::from("pay_by_agent"), Some(String::from("payments")));
+    assert!(
+        !contract
+            .simulate_pay_by_agent(accounts(1), accounts(2), NearToken::from_yoctonear(1))
+            .role_allowed
+    );
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_agent_without_role_can_still_heartbeat() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.set_required_role(String::from("pay_by_agent"), Some(String::from("payments")));
+
+    // the role gate only applies to pay_by_agent
+    set_context(accounts(1), 0);
+    let nonce = contract.issue_challenge(accounts(1));
+    let signature = {
+        use ed25519_dalek::Signer;
+        encode(test_signing_key().sign(nonce.as_bytes()).to_bytes())
+    };
+    assert!(contract.respond_challenge(nonce, signature));
+
+    set_context(accounts(0), 0);
+    contract.set_agent_role(accounts(1), Some(String::from("payments")));
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_reattestation_with_degraded_tcb_emits_event() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_contract();
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    assert!(get_logs().is_empty());
+
+    let mut worker = test_worker("codehash");
+    worker.tcb_status = String::from("OutOfDate");
+    contract.save_worker(accounts(1), worker);
+
+    let logs = get_logs();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[0].starts_with("EVENT_JSON:"));
+    assert!(logs[0].contains("\"event\":\"tcb_status_degraded\""));
+    assert!(logs[0].contains("\"old_status\":\"UpToDate\""));
+    assert!(logs[0].contains("\"new_status\":\"OutOfDate\""));
+    assert_eq!(contract.get_agent(accounts(1)).tcb_status, "OutOfDate");
+}
+
+#[test]
+fn test_fleet_advisories_aggregate_counts() {
+    use near_sdk::test_utils::accounts;
+
+    let advisories = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    let mut contract = setup_contract();
+
+    let mut worker = test_worker("codehash");
+    worker.advisory_ids = advisories(&["INTEL-SA-00615", "INTEL-SA-00828"]);
+    contract.save_worker(accounts(1), worker);
+    let mut worker = test_worker("codehash");
+    worker.advisory_ids = advisories(&["INTEL-SA-00828", "INTEL-SA-00828"]);
+    contract.save_worker(accounts(2), worker);
+
+    assert_eq!(
+        contract.fleet_advisories(10),
+        vec![
+            (String::from("INTEL-SA-00828"), 2),
+            (String::from("INTEL-SA-00615"), 1)
+        ]
+    );
+    assert_eq!(contract.fleet_advisories(1).len(), 1);
+
+    // re-registering without advisories releases that worker's counts
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    assert_eq!(
+        contract.fleet_advisories(10),
+        vec![(String::from("INTEL-SA-00828"), 1)]
+    );
+}
+
+#[test]
+fn test_max_payable_by_agent() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract
+        .worker_by_account_id
+        .insert(accounts(1), test_worker("codehash"));
+
+    // unapproved codehash is the binding constraint
+    assert_eq!(
+        contract.max_payable_by_agent(accounts(1)),
+        NearToken::from_yoctonear(0)
+    );
+
+    // otherwise the balance above the storage reserve is
+    contract.approve_codehash(String::from("codehash"));
+    let storage_reserve = env::storage_byte_cost().saturating_mul(env::storage_usage().into());
+    assert_eq!(
+        contract.max_payable_by_agent(accounts(1)),
+        env::account_balance().saturating_sub(storage_reserve)
+    );
+    assert!(contract.max_payable_by_agent(accounts(1)) < env::account_balance());
+
+    // a missing role is binding as well
+    contract.set_required_role(String::from("pay_by_agent"), Some(String::from("payments")));
+    assert_eq!(
+        contract.max_payable_by_agent(accounts(1)),
+        NearToken::from_yoctonear(0)
+    );
+}
+
+#[test]
+#[should_panic(expected = "worker is flagged as compromised")]
+fn test_flagged_worker_rejected_from_payouts() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_registered_agent();
+    contract.flag_worker_compromised(accounts(1));
+    assert!(get_logs()[0].contains("\"event\":\"worker_flagged\""));
+
+    let simulation =
+        contract.simulate_pay_by_agent(accounts(1), accounts(2), NearToken::from_yoctonear(1));
+    assert!(!simulation.not_compromised);
+    assert!(!simulation.would_succeed);
+
+    // re-registration does not clear the flag
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    assert!(contract.get_agent(accounts(1)).compromised);
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_unflagged_worker_can_pay_again() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.flag_worker_compromised(accounts(1));
+    contract.unflag_worker(accounts(1));
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+#[should_panic(expected = "register_agent needs at least 200 Tgas")]
+fn test_register_agent_rejects_insufficient_gas() {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    let mut contract = setup_contract();
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(1))
+        .prepaid_gas(Gas::from_tgas(50))
+        .build());
+
+    contract.register_agent(
+        String::new(),
+        String::new(),
+        String::from("checksum"),
+        String::new(),
+    );
+}
+
+#[test]
+fn test_worker_diff() {
+    let worker = test_worker("codehash");
+
+    // last_seen is volatile and does not count as a change
+    let mut seen_later = test_worker("codehash");
+    seen_later.last_seen = 100;
+    assert!(worker == seen_later);
+    assert!(worker.diff(&seen_later).is_empty());
+
+    let new_codehash = test_worker("new_codehash");
+    assert!(worker != new_codehash);
+    assert_eq!(worker.diff(&new_codehash).changed_fields, vec!["codehash"]);
+
+    let mut new_metadata = test_worker("codehash");
+    new_metadata.role = Some(String::from("payments"));
+    new_metadata.checksum = String::from("new_checksum");
+    assert_eq!(
+        worker.diff(&new_metadata).changed_fields,
+        vec!["checksum", "role"]
+    );
+}
+
+#[test]
+fn test_reregistration_emits_worker_updated() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_contract();
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    assert!(get_logs().is_empty());
+
+    contract.save_worker(accounts(1), test_worker("new_codehash"));
+    let logs = get_logs();
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].contains("\"event\":\"worker_updated\""));
+    assert!(logs[0].contains("\"changed_fields\":[\"codehash\"]"));
+}
+
+#[test]
+fn test_storage_growth_reduces_distributable_balance() {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    let contract = setup_contract();
+    let balance = NearToken::from_near(10);
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(0))
+        .account_balance(balance)
+        .storage_usage(1_000)
+        .build());
+    let small_state = contract.get_distributable_balance();
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(0))
+        .account_balance(balance)
+        .storage_usage(100_000)
+        .build());
+    let large_state = contract.get_distributable_balance();
+
+    assert!(large_state < small_state);
+    assert_eq!(
+        small_state.saturating_sub(large_state),
+        env::storage_byte_cost().saturating_mul(99_000)
+    );
+}
+
+#[test]
+#[should_panic(expected = "payout exceeds distributable balance")]
+fn test_pay_by_agent_keeps_storage_reserve() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.pay_by_agent(accounts(1), env::account_balance());
+}
+
+#[test]
+#[should_panic(expected = "codehash is banned: bad")]
+fn test_banned_codehash_cannot_be_approved() {
+    let mut contract = setup_contract();
+    assert!(contract.approve_codehash(String::from("bad")));
+    assert!(contract.ban_codehash(String::from("bad")));
+    assert!(!contract.approved_codehashes.contains("bad"));
+    assert!(contract.approve_codehash(String::from("good")));
+    contract.approve_codehash(String::from("bad"));
+}
+
+#[test]
+fn test_unbanned_codehash_can_be_approved() {
+    let mut contract = setup_contract();
+    contract.ban_codehash(String::from("bad"));
+    assert_eq!(contract.get_banned_codehashes(), vec![String::from("bad")]);
+    assert!(contract.unban_codehash(String::from("bad")));
+    assert!(contract.approve_codehash(String::from("bad")));
+}
+
+#[test]
+#[should_panic(expected = "account is not whitelisted for registration")]
+fn test_register_agent_rejects_non_whitelisted_account() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.enable_registration_whitelist();
+    contract.add_to_registration_whitelist(accounts(2));
+    assert!(!contract.is_registration_allowed(accounts(1)));
+
+    set_context(accounts(1), 0);
+    contract.register_agent(
+        String::new(),
+        String::new(),
+        String::from("checksum"),
+        String::new(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "tcb_info needs to be at most 16 bytes")]
+fn test_register_agent_allows_whitelisted_account() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    assert!(contract.is_registration_allowed(accounts(1)));
+    contract.enable_registration_whitelist();
+    assert!(contract.add_to_registration_whitelist(accounts(1)));
+    assert_eq!(contract.get_registration_whitelist(), Some(vec![accounts(1)]));
+    contract.set_max_tcb_info_bytes(16);
+
+    // gets past the whitelist and fails on the next check
+    set_context(accounts(1), 0);
+    contract.register_agent(
+        String::new(),
+        String::new(),
+        String::from("checksum"),
+        "x".repeat(17),
+    );
+}
+
+#[test]
+fn test_approve_codehash_respects_cap() {
+    let mut contract = setup_contract();
+    contract.set_max_approved_codehashes(2);
+    contract.approve_codehash(String::from("first"));
+    contract.approve_codehash(String::from("second"));
+    assert_eq!(contract.get_approved_codehash_capacity(), (2, 2));
+
+    // re-approving at the cap is a no-op rather than an error
+    assert!(!contract.approve_codehash(String::from("second")));
+
+    contract.remove_codehash(String::from("first"));
+    assert!(contract.approve_codehash(String::from("third")));
+}
+
+#[test]
+#[should_panic(expected = "cannot approve more than 2 codehashes")]
+fn test_approve_codehash_rejects_beyond_cap() {
+    let mut contract = setup_contract();
+    contract.set_max_approved_codehashes(2);
+    contract.approve_codehash(String::from("first"));
+    contract.approve_codehash(String::from("second"));
+    contract.approve_codehash(String::from("third"));
+}
+
+#[test]
+fn test_owner_bypass_pays_without_agent() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    let amount = NearToken::from_yoctonear(1);
+    let simulation = contract.simulate_pay_by_agent(accounts(0), accounts(1), amount);
+    assert!(simulation.owner_bypass && !simulation.agent_registered);
+    assert!(simulation.would_succeed);
+    assert_eq!(
+        contract.max_payable_by_agent(accounts(0)),
+        contract.get_distributable_balance()
+    );
+
+    contract.pay_by_agent(accounts(1), amount);
+}
+
+#[test]
+#[should_panic(expected = "no worker found")]
+fn test_owner_bypass_disabled_rejects_owner() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.set_owner_bypass_enabled(false);
+    assert!(
+        !contract
+            .simulate_pay_by_agent(accounts(0), accounts(1), NearToken::from_yoctonear(1))
+            .would_succeed
+    );
+    assert_eq!(contract.max_payable_by_agent(accounts(0)).as_yoctonear(), 0);
+
+    contract.pay_by_agent(accounts(1), NearToken::from_yoctonear(1));
+}
+
+#[test]
+#[should_panic(expected = "codehash not approved: codehash")]
+fn test_pay_by_agent_requires_approved_codehash() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract
+        .worker_by_account_id
+        .insert(accounts(1), test_worker("codehash"));
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_revert_registration_within_window() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_contract();
+    let mut worker = test_worker("codehash");
+    worker.registered_at = 1_000;
+    worker.advisory_ids = vec![String::from("INTEL-SA-00001")];
+    contract.save_worker(accounts(1), worker);
+    assert_eq!(contract.fleet_advisories(10).len(), 1);
+
+    set_context(accounts(1), 1_000 + 10 * 60);
+    contract.revert_registration();
+    assert!(get_logs()[0].contains("\"event\":\"registration_reverted\""));
+    assert!(contract.worker_by_account_id.get(&accounts(1)).is_none());
+    assert!(contract.fleet_advisories(10).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "worker is flagged as compromised")]
+fn test_flagged_worker_cannot_revert_registration() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    let mut worker = test_worker("codehash");
+    worker.registered_at = 1_000;
+    contract.save_worker(accounts(1), worker);
+    contract.flag_worker_compromised(accounts(1));
+
+    set_context(accounts(1), 1_000);
+    contract.revert_registration();
+}
+
+#[test]
+#[should_panic(expected = "registration was already reverted once")]
+fn test_revert_registration_only_once() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    let mut worker = test_worker("codehash");
+    worker.registered_at = 1_000;
+    contract.save_worker(accounts(1), worker.clone());
+    set_context(accounts(1), 1_000);
+    contract.revert_registration();
+
+    // registering again opens no new undo window
+    contract.save_worker(accounts(1), worker);
+    contract.revert_registration();
+}
+
+#[test]
+#[should_panic(expected = "registration undo window has passed")]
+fn test_revert_registration_after_window() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    let mut worker = test_worker("codehash");
+    worker.registered_at = 1_000;
+    contract.save_worker(accounts(1), worker);
+
+    // re-registering keeps the original registration time
+    set_context(accounts(0), 1_500);
+    contract.save_worker(accounts(1), test_worker("codehash"));
+    assert_eq!(contract.get_agent(accounts(1)).registered_at, 1_000);
+
+    set_context(accounts(1), 1_000 + 10 * 60 + 1);
+    contract.revert_registration();
+}
+
+#[test]
+fn test_codehash_rtmr_index_selects_register() {
+    let mut contract = setup_contract();
+    let rtmrs = [[0u8; 48], [1u8; 48], [2u8; 48], [3u8; 48]];
+    assert_eq!(contract.get_codehash_rtmr_index(), 3);
+    assert_eq!(
+        collateral::select_rtmr(&rtmrs, contract.get_codehash_rtmr_index()),
+        encode([3u8; 48])
+    );
+
+    contract.set_codehash_rtmr_index(2);
+    assert_eq!(
+        collateral::select_rtmr(&rtmrs, contract.get_codehash_rtmr_index()),
+        encode([2u8; 48])
+    );
+}
+
+#[test]
+#[should_panic(expected = "rtmr index needs to be between 0 and 3")]
+fn test_set_codehash_rtmr_index_rejects_out_of_range() {
+    let mut contract = setup_contract();
+    contract.set_codehash_rtmr_index(4);
+}
+
+#[test]
+#[should_panic(expected = "predecessor_account_id != report_data")]
+fn test_approve_and_register_checks_owner_binding() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    let (quote_hex, collateral) = collateral::sample_quote();
+    set_context(accounts(0), collateral::SAMPLE_QUOTE_TIME);
+
+    // the sample quote verifies but binds another account, so registration stops at report_data
+    contract.approve_and_register(
+        vec![String::from("api_image"), String::from("app_image")],
+        quote_hex,
+        collateral,
+        String::from("checksum"),
+        String::new(),
+    );
+}
+
+#[test]
+fn test_approve_and_register_from_empty_allowlist() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    assert_eq!(contract.get_approved_codehash_capacity().0, 0);
+    let (api_image, app_image) = (encode([1u8; 32]), encode([2u8; 32]));
+    let (tcb_info, rtmr3) = collateral::shade_agent_tcb_info(&api_image, &app_image);
+
+    // the sample quote verifies, its report is then bound to the owner and the app_compose above
+    let mut report_data = accounts(0).as_bytes().to_vec();
+    report_data.resize(64, 0);
+    TEST_ATTESTED_FIELDS.with(|fields| {
+        *fields.borrow_mut() = Some((report_data, [[0u8; 48], [0u8; 48], [0u8; 48], rtmr3]))
+    });
+
+    let (quote_hex, collateral) = collateral::sample_quote();
+    set_context(accounts(0), collateral::SAMPLE_QUOTE_TIME);
+    assert!(contract.approve_and_register(
+        vec![api_image.clone(), app_image.clone()],
+        quote_hex,
+        collateral,
+        String::from("checksum"),
+        tcb_info,
+    ));
+
+    assert!(contract.approved_codehashes.contains(&api_image));
+    assert!(contract.approved_codehashes.contains(&app_image));
+    let worker = contract.get_agent(accounts(0));
+    assert_eq!(worker.codehash, app_image);
+    assert_eq!(worker.registered_at, collateral::SAMPLE_QUOTE_TIME);
+}
+
+#[test]
+#[should_panic]
+fn test_approve_and_register_requires_owner() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    set_context(accounts(1), 0);
+    contract.approve_and_register(
+        vec![String::from("app_image")],
+        String::new(),
+        String::new(),
+        String::from("checksum"),
+        String::new(),
+    );
+}
+
+#[test]
+fn test_matches_claimed_codehash() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+
+    assert!(contract.matches_approved_codehash(String::from("codehash")));
+    assert!(!contract.matches_approved_codehash(String::from("local_build")));
+    assert!(contract.matches_worker(accounts(1), String::from("codehash")));
+    assert!(!contract.matches_worker(accounts(1), String::from("local_build")));
+    assert!(!contract.matches_worker(accounts(2), String::from("codehash")));
+}
+
+#[test]
+#[should_panic(expected = "worker needs to re-attest")]
+fn test_force_reattest_all_expires_workers() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_registered_agent();
+
+    assert_eq!(contract.force_reattest_all(), 1);
+    assert!(get_logs()[0].contains("\"event\":\"reattestation_forced\""));
+    assert!(
+        !contract
+            .simulate_pay_by_agent(accounts(1), accounts(2), NearToken::from_yoctonear(1))
+            .attestation_current
+    );
+    assert_eq!(contract.max_payable_by_agent(accounts(1)).as_yoctonear(), 0);
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_reattested_worker_can_pay_again() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+    contract.force_reattest_all();
+
+    // register_agent stamps the current epoch on the re-attested worker
+    let mut reattested = test_worker("codehash");
+    reattested.attestation_epoch = contract.get_attestation_epoch();
+    contract.save_worker(accounts(1), reattested);
+
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_yoctonear(1));
+}
+
+#[test]
+fn test_utf8_report_data_does_not_bind_length_prefixed_reading() {
+    use near_sdk::test_utils::accounts;
+
+    // a 64 char implicit account id starting with a digit reads as a length prefix
+    let victim: AccountId = "5".repeat(64).parse().unwrap();
+    let mut report_data = [0u8; 64];
+    report_data.copy_from_slice(victim.as_bytes());
+    let substring = decode_report_data(ReportDataFormat::LengthPrefixed, &report_data);
+    let attacker: AccountId = substring.parse().unwrap();
+
+    let mut contract = setup_contract();
+    assert!(contract.report_data_binds(&victim, &report_data));
+    assert!(!contract.report_data_binds(&attacker, &report_data));
+
+    // a per-agent format only applies to the account the owner set it for
+    contract.set_agent_report_data_format(accounts(1), Some(ReportDataFormat::LengthPrefixed));
+    assert_eq!(
+        contract.get_agent_report_data_format(accounts(1)),
+        ReportDataFormat::LengthPrefixed
+    );
+    assert!(!contract.report_data_binds(&attacker, &report_data));
+}
+
+#[test]
+fn test_owner_bypass_skips_role_gate() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_contract();
+    contract.set_required_role(String::from("pay_by_agent"), Some(String::from("payments")));
+
+    let amount = NearToken::from_yoctonear(1);
+    let simulation = contract.simulate_pay_by_agent(accounts(0), accounts(1), amount);
+    assert!(simulation.role_allowed);
+    assert!(simulation.would_succeed);
+    assert_eq!(
+        contract.max_payable_by_agent(accounts(0)),
+        contract.get_distributable_balance()
+    );
+
+    contract.pay_by_agent(accounts(1), amount);
+}
+
+// (name, kind, [(param, type)], returns) with types spelled as in abi.json
+#[cfg(test)]
+type AbiMethod = (String, String, Vec<(String, String)>, Option<String>);
+
+// The public methods of every `#[near] impl Contract` block in a source file
+#[cfg(test)]
+fn exported_methods(path: &std::path::Path) -> Vec<AbiMethod> {
+    use quote::ToTokens;
+
+    let has_attr =
+        |attrs: &[syn::Attribute], name: &str| attrs.iter().any(|attr| attr.path().is_ident(name));
+    let type_name = |ty: &syn::Type| ty.to_token_stream().to_string().replace(' ', "");
+
+    let source = std::fs::read_to_string(path).unwrap();
+    let mut methods = Vec::new();
+    for item in syn::parse_file(&source).unwrap().items {
+        let syn::Item::Impl(block) = item else {
+            continue;
+        };
+        if !has_attr(&block.attrs, "near") || type_name(&block.self_ty) != "Contract" {
+            continue;
+        }
+
+        for item in block.items {
+            let syn::ImplItem::Fn(method) = item else {
+                continue;
+            };
+            if !matches!(method.vis, syn::Visibility::Public(_)) {
+                continue;
+            }
+
+            let mut kind = "view";
+            let mut params = Vec::new();
+            for arg in &method.sig.inputs {
+                match arg {
+                    syn::FnArg::Receiver(receiver) if receiver.mutability.is_some() => kind = "call",
+                    syn::FnArg::Receiver(_) => {}
+                    syn::FnArg::Typed(arg) => {
+                        params.push((arg.pat.to_token_stream().to_string(), type_name(&arg.ty)))
+                    }
+                }
+            }
+            // the ABI describes what a promise resolves to, and init returns nothing
+            let returns = match &method.sig.output {
+                syn::ReturnType::Type(_, ty) if !has_attr(&method.attrs, "init") => {
+                    let ty = type_name(ty);
+                    Some(
+                        ty.strip_prefix("PromiseOrValue<")
+                            .and_then(|inner| inner.strip_suffix('>'))
+                            .map_or(ty.clone(), String::from),
+                    )
+                }
+                _ => None,
+            };
+            if has_attr(&method.attrs, "init") {
+                kind = "init";
+            }
+            methods.push((method.sig.ident.to_string(), kind.to_string(), params, returns));
+        }
+    }
+    methods
+}
+
+#[test]
+fn test_contract_abi_matches_source() {
+    let type_name = |ty: &serde_json::Value| ty.as_str().map(|ty| ty.replace(' ', ""));
+
+    let abi = setup_contract().get_contract_abi();
+    let mut documented: Vec<AbiMethod> = abi["methods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|method| {
+            let params = method["params"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|param| {
+                    let name = param["name"].as_str().unwrap().to_string();
+                    (name, type_name(&param["type"]).unwrap())
+                })
+                .collect();
+            (
+                method["name"].as_str().unwrap().to_string(),
+                method["kind"].as_str().unwrap().to_string(),
+                params,
+                type_name(&method["returns"]),
+            )
+        })
+        .collect();
+
+    let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut exported: Vec<AbiMethod> = std::fs::read_dir(src)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .flat_map(|path| exported_methods(&path))
+        .collect();
+
+    documented.sort();
+    exported.sort();
+    assert_eq!(documented, exported, "abi.json is out of date");
+}
+
+#[test]
+#[should_panic(expected = "unknown tcb status: UptoDate")]
+fn test_set_tcb_policy_rejects_unknown_status() {
+    let mut contract = setup_contract();
+    contract.set_tcb_policy(TcbPolicy {
+        max_tcb_status: String::from("UptoDate"),
+        ..TcbPolicy::default()
+    });
 }
@@ -0,0 +1,158 @@
+use crate::*;
+
+const CHALLENGE_TTL_SECONDS: u64 = 600;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Challenge {
+    pub nonce: String,
+    pub issued_at: u64,
+}
+
+#[near]
+impl Contract {
+    /// Issues a fresh nonce the worker has to sign with its registered key.
+    /// Issuing a new challenge replaces any outstanding one for the same worker, so only the
+    /// owner and the worker itself can issue one.
+    pub fn issue_challenge(&mut self, account_id: AccountId) -> String {
+        let predecessor = env::predecessor_account_id();
+        require!(
+            predecessor == self.owner_id || predecessor == account_id,
+            "only the owner or the worker can issue a challenge"
+        );
+        require!(
+            self.worker_by_account_id.contains_key(&account_id),
+            "no worker found"
+        );
+
+        let mut seed = env::random_seed();
+        seed.extend_from_slice(account_id.as_bytes());
+        let nonce = encode(env::sha256(&seed));
+
+        self.challenge_by_account_id.insert(
+            account_id,
+            Challenge {
+                nonce: nonce.clone(),
+                issued_at: block_timestamp() / 1000000000,
+            },
+        );
+
+        nonce
+    }
+
+    // Function for the worker to answer its outstanding challenge with a hex encoded ed25519 signature
+    pub fn respond_challenge(&mut self, nonce: String, signature: String) -> bool {
+        let account_id = env::predecessor_account_id();
+        let challenge = self
+            .challenge_by_account_id
+            .get(&account_id)
+            .expect("no challenge issued")
+            .clone();
+        let now = block_timestamp() / 1000000000;
+
+        require!(challenge.nonce == nonce, "challenge nonce mismatch");
+        require!(
+            now <= challenge.issued_at + CHALLENGE_TTL_SECONDS,
+            "challenge expired"
+        );
+
+        let worker = self
+            .worker_by_account_id
+            .get_mut(&account_id)
+            .expect("no worker found");
+        require!(
+            verify_signature(&worker.public_key, nonce.as_bytes(), &signature),
+            "invalid challenge signature"
+        );
+        worker.last_seen = now;
+
+        self.challenge_by_account_id.remove(&account_id);
+
+        true
+    }
+
+    pub fn get_challenge(&self, account_id: AccountId) -> Option<Challenge> {
+        self.challenge_by_account_id.get(&account_id).cloned()
+    }
+}
+
+pub fn verify_signature(public_key: &PublicKey, message: &[u8], signature: &str) -> bool {
+    if public_key.curve_type() != CurveType::ED25519 {
+        return false;
+    }
+    // the first byte of the key data is the curve type
+    let Ok(public_key) = <[u8; 32]>::try_from(&public_key.as_bytes()[1..]) else {
+        return false;
+    };
+    let Ok(signature) = decode(signature) else {
+        return false;
+    };
+    let Ok(signature) = <[u8; 64]>::try_from(signature.as_slice()) else {
+        return false;
+    };
+
+    env::ed25519_verify(&signature, message, &public_key)
+}
+
+#[cfg(test)]
+fn sign(signing_key: &ed25519_dalek::SigningKey, message: &str) -> String {
+    use ed25519_dalek::Signer;
+
+    encode(signing_key.sign(message.as_bytes()).to_bytes())
+}
+
+#[test]
+fn test_respond_challenge_updates_last_seen() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+
+    set_context(accounts(1), 100);
+    let nonce = contract.issue_challenge(accounts(1));
+    assert!(contract.respond_challenge(nonce.clone(), sign(&test_signing_key(), &nonce)));
+
+    assert_eq!(contract.get_agent(accounts(1)).last_seen, 100);
+    assert!(contract.get_challenge(accounts(1)).is_none());
+}
+
+#[test]
+#[should_panic(expected = "invalid challenge signature")]
+fn test_respond_challenge_wrong_key() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+
+    set_context(accounts(1), 100);
+    let nonce = contract.issue_challenge(accounts(1));
+    let wrong_key = ed25519_dalek::SigningKey::from_bytes(&[2u8; 32]);
+    contract.respond_challenge(nonce.clone(), sign(&wrong_key, &nonce));
+}
+
+#[test]
+#[should_panic(expected = "challenge expired")]
+fn test_respond_challenge_stale_nonce() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+
+    set_context(accounts(1), 100);
+    let nonce = contract.issue_challenge(accounts(1));
+
+    set_context(accounts(1), 100 + CHALLENGE_TTL_SECONDS + 1);
+    contract.respond_challenge(nonce.clone(), sign(&test_signing_key(), &nonce));
+}
+
+#[test]
+#[should_panic(expected = "only the owner or the worker can issue a challenge")]
+fn test_issue_challenge_rejects_third_party() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_registered_agent();
+
+    set_context(accounts(0), 100);
+    let nonce = contract.issue_challenge(accounts(1));
+    assert_eq!(contract.get_challenge(accounts(1)).unwrap().nonce, nonce);
+
+    set_context(accounts(2), 100);
+    contract.issue_challenge(accounts(1));
+}
@@ -0,0 +1,407 @@
+use crate::*;
+use near_sdk::PromiseResult;
+
+// every pending request is stored at the vault's expense
+const MAX_PENDING_PAYOUTS_PER_AGENT: usize = 10;
+
+/// What happens to a payout larger than the distributable balance.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayoutMode {
+    /// reject the payout
+    Strict,
+    /// pay out the distributable balance instead
+    BestEffort,
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct PendingPayout {
+    pub agent: AccountId,
+    pub recipient: AccountId,
+    pub amount: NearToken,
+    pub requested_at: u64,
+}
+
+#[near]
+impl Contract {
+    pub fn set_payout_mode(&mut self, mode: PayoutMode) {
+        self.require_owner();
+        self.payout_mode = mode;
+    }
+
+    pub fn get_payout_mode(&self) -> PayoutMode {
+        self.payout_mode
+    }
+
+    /// Sets the amount from which agent payouts need owner approval, `None` disables approvals.
+    pub fn set_payout_approval_threshold(&mut self, threshold: Option<NearToken>) {
+        self.require_owner();
+        self.payout_approval_threshold = threshold;
+    }
+
+    // Function for the agent to file a payout that the owner has to approve
+    pub fn request_payout(&mut self, recipient: AccountId, amount: NearToken) -> u32 {
+        self.require_approved_codehash();
+        self.require_role("request_payout");
+
+        let agent = env::predecessor_account_id();
+        require!(
+            !self.agent_transfer_to_self_forbidden || recipient != agent,
+            "agent cannot pay itself"
+        );
+        require!(
+            self.get_agent(agent.clone()).allows_recipient(&recipient),
+            format!("recipient not allowed for agent: {}", recipient)
+        );
+        let pending_for_agent = self
+            .pending_payouts
+            .values()
+            .filter(|payout| payout.agent == agent)
+            .count();
+        require!(
+            pending_for_agent < MAX_PENDING_PAYOUTS_PER_AGENT,
+            format!(
+                "agent can have at most {} pending payouts",
+                MAX_PENDING_PAYOUTS_PER_AGENT
+            )
+        );
+
+        self.current_payout_request_id += 1;
+        let request_id = self.current_payout_request_id;
+
+        self.pending_payouts.insert(
+            request_id,
+            PendingPayout {
+                agent: agent.clone(),
+                recipient: recipient.clone(),
+                amount,
+                requested_at: block_timestamp() / 1000000000,
+            },
+        );
+
+        ContractEvent::PayoutRequested {
+            request_id,
+            agent,
+            recipient,
+            amount,
+        }
+        .emit();
+
+        request_id
+    }
+
+    pub fn approve_payout(&mut self, request_id: u32) -> PromiseOrValue<bool> {
+        self.require_owner();
+        let payout = self
+            .pending_payouts
+            .remove(&request_id)
+            .expect("Payout request not found or already processed");
+        // the agent may have been flagged or expired since it filed the request
+        self.require_approved_worker(&payout.agent);
+        require!(
+            self.has_required_role("request_payout", &payout.agent),
+            "agent lacks the role required for request_payout"
+        );
+        let amount = self.payable_amount(&payout.recipient, payout.amount);
+
+        ContractEvent::PayoutApproved { request_id }.emit();
+        self.transfer_with_callback(payout.recipient, amount)
+    }
+
+    pub fn reject_payout(&mut self, request_id: u32) {
+        self.require_owner();
+        self.pending_payouts
+            .remove(&request_id)
+            .expect("Payout request not found or already processed");
+
+        ContractEvent::PayoutRejected { request_id }.emit();
+    }
+
+    #[private]
+    pub fn on_payout_complete(&mut self, recipient: AccountId, amount: NearToken) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if !succeeded {
+            log!("Payout of {:?} yoctoNEAR to {:?} failed", amount, recipient);
+        }
+        succeeded
+    }
+
+    pub fn get_pending_payouts(
+        &self,
+        from_index: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<(u32, PendingPayout)> {
+        let from = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(self.pending_payouts.len());
+
+        // removals swap entries around, so the map does not iterate in id order
+        let mut payouts: Vec<(u32, PendingPayout)> = self
+            .pending_payouts
+            .iter()
+            .filter(|(id, _)| **id >= from)
+            .map(|(id, payout)| (*id, payout.clone()))
+            .collect();
+        payouts.sort_by_key(|(id, _)| *id);
+        payouts.truncate(limit as usize);
+        payouts
+    }
+}
+
+impl Contract {
+    /// Applies the payout mode to a requested amount, the storage reserve is recomputed on every
+    /// payout so a grown state can't be drained.
+    pub(crate) fn payable_amount(&self, recipient: &AccountId, amount: NearToken) -> NearToken {
+        let distributable = self.distributable_balance();
+        if amount <= distributable {
+            return amount;
+        }
+
+        require!(
+            self.payout_mode == PayoutMode::BestEffort,
+            "payout exceeds distributable balance"
+        );
+        require!(!distributable.is_zero(), "no distributable balance to pay out");
+        ContractEvent::PayoutCapped {
+            recipient: recipient.clone(),
+            requested: amount,
+            paid: distributable,
+        }
+        .emit();
+        distributable
+    }
+
+    /// Zero amounts are skipped instead of spending gas on a no-op transfer.
+    pub(crate) fn transfer_with_callback(
+        &self,
+        recipient: AccountId,
+        amount: NearToken,
+    ) -> PromiseOrValue<bool> {
+        if amount.is_zero() {
+            log!("Skipping zero amount payout to {:?}", recipient);
+            return PromiseOrValue::Value(true);
+        }
+
+        Promise::new(recipient.clone())
+            .transfer(amount)
+            .then(self.callback().on_payout_complete(recipient, amount))
+            .into()
+    }
+}
+
+#[cfg(test)]
+fn setup_approval_flow() -> Contract {
+    let mut contract = setup_registered_agent();
+    contract.set_payout_approval_threshold(Some(NearToken::from_near(1)));
+    contract
+}
+
+#[test]
+#[should_panic(expected = "payout requires owner approval")]
+fn test_pay_by_agent_above_threshold_needs_approval() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    contract.pay_by_agent(accounts(2), NearToken::from_millinear(999));
+    contract.pay_by_agent(accounts(2), NearToken::from_near(1));
+}
+
+#[test]
+fn test_request_and_approve_payout() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    let request_id = contract.request_payout(accounts(2), NearToken::from_near(2));
+
+    let pending = contract.get_pending_payouts(None, None);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].0, request_id);
+    assert_eq!(pending[0].1.recipient, accounts(2));
+
+    set_context(accounts(0), 0);
+    contract.approve_payout(request_id);
+    assert!(get_logs()[0].contains("\"event\":\"payout_approved\""));
+    assert!(contract.get_pending_payouts(None, None).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Payout request not found or already processed")]
+fn test_rejected_payout_cannot_be_approved() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    let request_id = contract.request_payout(accounts(2), NearToken::from_near(2));
+
+    set_context(accounts(0), 0);
+    contract.reject_payout(request_id);
+    assert!(contract.get_pending_payouts(None, None).is_empty());
+    contract.approve_payout(request_id);
+}
+
+#[cfg(test)]
+fn set_transfer_result(result: PromiseResult) {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig};
+
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        near_sdk::test_vm_config(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![result],
+    );
+}
+
+#[test]
+fn test_on_payout_complete_reports_transfer_result() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_contract();
+    contract.pay_by_agent(accounts(1), NearToken::from_near(1));
+
+    set_transfer_result(PromiseResult::Successful(vec![]));
+    assert!(contract.on_payout_complete(accounts(1), NearToken::from_near(1)));
+
+    set_transfer_result(PromiseResult::Failed);
+    assert!(!contract.on_payout_complete(accounts(1), NearToken::from_near(1)));
+    assert!(get_logs()[0].contains("failed"));
+}
+
+#[test]
+#[should_panic(expected = "payout exceeds distributable balance")]
+fn test_strict_payout_mode_rejects_approved_payout_above_balance() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    assert_eq!(contract.get_payout_mode(), PayoutMode::Strict);
+    set_context(accounts(1), 0);
+    let request_id = contract.request_payout(accounts(2), env::account_balance());
+
+    // the balance is only checked once the owner approves
+    set_context(accounts(0), 0);
+    contract.approve_payout(request_id);
+}
+
+#[test]
+fn test_best_effort_payout_mode_caps_payout() {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    let mut contract = setup_contract();
+    contract.set_payout_mode(PayoutMode::BestEffort);
+    let requested = env::account_balance();
+    let simulation = contract.simulate_pay_by_agent(accounts(0), accounts(1), requested);
+    assert!(!simulation.sufficient_balance);
+    assert!(simulation.would_succeed);
+
+    contract.pay_by_agent(accounts(1), requested);
+    let logs = get_logs();
+    let capped = logs
+        .iter()
+        .find(|log| log.contains("\"event\":\"payout_capped\""))
+        .expect("payout_capped event");
+    assert!(capped.contains(&contract.get_distributable_balance().as_yoctonear().to_string()));
+}
+
+#[test]
+#[should_panic(expected = "no distributable balance to pay out")]
+fn test_best_effort_payout_mode_rejects_empty_vault() {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    let mut contract = setup_contract();
+    contract.set_payout_mode(PayoutMode::BestEffort);
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(accounts(0))
+        .account_balance(NearToken::from_yoctonear(0))
+        .build());
+    assert!(contract.get_distributable_balance().is_zero());
+    let simulation = contract.simulate_pay_by_agent(accounts(0), accounts(1), NearToken::from_near(1));
+    assert!(!simulation.would_succeed);
+
+    contract.pay_by_agent(accounts(1), NearToken::from_near(1));
+}
+
+#[test]
+fn test_payout_callback_uses_configured_gas() {
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+
+    let mut contract = setup_contract();
+    contract.set_callback_gas(Gas::from_tgas(12));
+    assert_eq!(contract.get_callback_gas(), Gas::from_tgas(12));
+    contract.pay_by_agent(accounts(1), NearToken::from_yoctonear(1));
+
+    assert!(get_created_receipts().into_iter().any(|action| matches!(
+        action,
+        MockAction::FunctionCallWeight { method_name, prepaid_gas, .. }
+            if method_name == b"on_payout_complete" && prepaid_gas == Gas::from_tgas(12)
+    )));
+}
+
+#[test]
+fn test_zero_amount_payout_skips_transfer() {
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+
+    let mut contract = setup_contract();
+    let result = contract.pay_by_agent(accounts(1), NearToken::from_yoctonear(0));
+
+    assert!(matches!(result, PromiseOrValue::Value(true)));
+    assert!(get_created_receipts().is_empty());
+}
+
+#[test]
+fn test_get_pending_payouts_pages_in_id_order() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    let ids: Vec<u32> = (0..4)
+        .map(|_| contract.request_payout(accounts(2), NearToken::from_near(2)))
+        .collect();
+
+    // removing the first request moves the last one into its slot
+    set_context(accounts(0), 0);
+    contract.reject_payout(ids[0]);
+
+    let first_page = contract.get_pending_payouts(None, Some(2));
+    let next = first_page.last().unwrap().0 + 1;
+    let second_page = contract.get_pending_payouts(Some(next), Some(2));
+    let paged: Vec<u32> = first_page
+        .iter()
+        .chain(second_page.iter())
+        .map(|(id, _)| *id)
+        .collect();
+    assert_eq!(paged, ids[1..].to_vec());
+}
+
+#[test]
+#[should_panic(expected = "worker is flagged as compromised")]
+fn test_approve_payout_rechecks_agent() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    let request_id = contract.request_payout(accounts(2), NearToken::from_near(2));
+
+    set_context(accounts(0), 0);
+    contract.flag_worker_compromised(accounts(1));
+    contract.approve_payout(request_id);
+}
+
+#[test]
+#[should_panic(expected = "agent can have at most 10 pending payouts")]
+fn test_request_payout_caps_pending_per_agent() {
+    use near_sdk::test_utils::accounts;
+
+    let mut contract = setup_approval_flow();
+    set_context(accounts(1), 0);
+    for _ in 0..=MAX_PENDING_PAYOUTS_PER_AGENT {
+        contract.request_payout(accounts(2), NearToken::from_near(2));
+    }
+}
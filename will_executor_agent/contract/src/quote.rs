@@ -0,0 +1,39 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuoteType {
+    Sgx,
+    Tdx,
+}
+
+const TEE_TYPE_SGX: u32 = 0x00000000;
+const TEE_TYPE_TDX: u32 = 0x00000081;
+
+/// Classifies a raw DCAP quote from its header: version (u16 LE) at offset 0 and
+/// TEE type (u32 LE) at offset 4. Returns `None` for short or unknown headers.
+pub fn detect_quote_type(bytes: &[u8]) -> Option<QuoteType> {
+    let version = u16::from_le_bytes(bytes.get(0..2)?.try_into().ok()?);
+    let tee_type = u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?);
+
+    match (version, tee_type) {
+        (3..=5, TEE_TYPE_SGX) => Some(QuoteType::Sgx),
+        (4..=5, TEE_TYPE_TDX) => Some(QuoteType::Tdx),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_detect_quote_type() {
+    use hex::decode;
+
+    // version 4, attestation key type 2, TEE type 0x81
+    let tdx = decode("040002008100000000000000").unwrap();
+    assert_eq!(detect_quote_type(&tdx), Some(QuoteType::Tdx));
+
+    // version 3, attestation key type 2, TEE type 0
+    let sgx = decode("030002000000000000000000").unwrap();
+    assert_eq!(detect_quote_type(&sgx), Some(QuoteType::Sgx));
+
+    assert_eq!(detect_quote_type(&tdx[..6]), None);
+    assert_eq!(detect_quote_type(&[]), None);
+    let unknown_tee = decode("040002007f00000000000000").unwrap();
+    assert_eq!(detect_quote_type(&unknown_tee), None);
+}
@@ -0,0 +1,106 @@
+use crate::*;
+use sha2::{Digest as _, Sha256};
+
+/// How the enclave encodes the account binding inside the 64 byte `report_data` field.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportDataFormat {
+    /// raw UTF-8 account id, zero padded
+    Utf8,
+    /// hex encoded UTF-8 account id, zero padded
+    Hex,
+    /// one length byte followed by the UTF-8 account id
+    LengthPrefixed,
+    /// sha256 of the UTF-8 account id in the first 32 bytes, for ids that don't fit
+    Sha256,
+}
+
+/// Checks that `report_data` binds `account_id` in the given format.
+pub fn report_data_matches(format: ReportDataFormat, report_data: &[u8], account_id: &str) -> bool {
+    match format {
+        ReportDataFormat::Sha256 => {
+            let digest: [u8; 32] = Sha256::digest(account_id.as_bytes()).into();
+            report_data.get(..32) == Some(digest.as_slice())
+        }
+        _ => decode_report_data(format, report_data) == account_id,
+    }
+}
+
+/// Decodes the bound account id, for `Sha256` this is the hex encoded hash.
+pub fn decode_report_data(format: ReportDataFormat, report_data: &[u8]) -> String {
+    let bytes = match format {
+        ReportDataFormat::Utf8 => trim_padding(report_data).to_vec(),
+        ReportDataFormat::Hex => {
+            decode(trim_padding(report_data)).expect("report_data should be hex encoded")
+        }
+        ReportDataFormat::LengthPrefixed => {
+            let (len, rest) = report_data
+                .split_first()
+                .expect("report_data should not be empty");
+            let len = *len as usize;
+            require!(len <= rest.len(), "report_data length prefix out of bounds");
+            rest[..len].to_vec()
+        }
+        ReportDataFormat::Sha256 => {
+            require!(report_data.len() >= 32, "report_data should hold a sha256 hash");
+            return encode(&report_data[..32]);
+        }
+    };
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// helpers
+
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    &bytes[..end]
+}
+
+#[cfg(test)]
+fn padded(bytes: &[u8]) -> [u8; 64] {
+    let mut report_data = [0u8; 64];
+    report_data[..bytes.len()].copy_from_slice(bytes);
+    report_data
+}
+
+#[test]
+fn test_decode_report_data_formats() {
+    let account_id = "agent.testnet";
+
+    let utf8 = padded(account_id.as_bytes());
+    assert_eq!(decode_report_data(ReportDataFormat::Utf8, &utf8), account_id);
+
+    let hex = padded(encode(account_id).as_bytes());
+    assert_eq!(decode_report_data(ReportDataFormat::Hex, &hex), account_id);
+
+    let mut length_prefixed = vec![account_id.len() as u8];
+    length_prefixed.extend_from_slice(account_id.as_bytes());
+    let length_prefixed = padded(&length_prefixed);
+    assert_eq!(
+        decode_report_data(ReportDataFormat::LengthPrefixed, &length_prefixed),
+        account_id
+    );
+}
+
+#[test]
+#[should_panic(expected = "report_data length prefix out of bounds")]
+fn test_decode_report_data_length_prefix_out_of_bounds() {
+    let mut report_data = [0u8; 64];
+    report_data[0] = 64;
+    decode_report_data(ReportDataFormat::LengthPrefixed, &report_data);
+}
+
+#[test]
+fn test_report_data_sha256_binding() {
+    let account_id = "a-very-long-agent-account-name-that-does-not-fit.testnet";
+    let digest: [u8; 32] = Sha256::digest(account_id.as_bytes()).into();
+    let report_data = padded(&digest);
+
+    assert!(report_data_matches(ReportDataFormat::Sha256, &report_data, account_id));
+    assert!(!report_data_matches(ReportDataFormat::Sha256, &report_data, "agent.testnet"));
+    assert_eq!(
+        decode_report_data(ReportDataFormat::Sha256, &report_data),
+        encode(digest)
+    );
+}
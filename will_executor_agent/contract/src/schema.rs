@@ -0,0 +1,36 @@
+use crate::*;
+use schemars::schema_for;
+
+/// JSON schemas of the public contract types. `#[near(serializers = [json])]` derives
+/// `JsonSchema` for them when near-sdk's `abi` feature is enabled through `schema`.
+pub fn type_schemas() -> serde_json::Value {
+    serde_json::json!({
+        "Worker": schema_for!(Worker),
+        "PayoutSimulation": schema_for!(PayoutSimulation),
+        "Challenge": schema_for!(Challenge),
+        "ReportDataFormat": schema_for!(ReportDataFormat),
+        "TcbDecision": schema_for!(TcbDecision),
+        "PendingPayout": schema_for!(PendingPayout),
+        "PayoutMode": schema_for!(PayoutMode),
+        "TcbPolicy": schema_for!(TcbPolicy),
+    })
+}
+
+#[test]
+fn test_type_schemas() {
+    let schemas = type_schemas();
+
+    for name in [
+        "Worker",
+        "PayoutSimulation",
+        "Challenge",
+        "ReportDataFormat",
+        "TcbDecision",
+        "PendingPayout",
+        "PayoutMode",
+        "TcbPolicy",
+    ] {
+        assert_eq!(schemas[name]["title"], name, "missing schema for {}", name);
+    }
+    assert!(schemas["Worker"]["properties"]["codehash"].is_object());
+}
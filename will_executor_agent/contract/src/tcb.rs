@@ -0,0 +1,292 @@
+use crate::*;
+
+// TCB statuses reported by dcap-qvl, from best to worst
+const TCB_STATUS_ORDER: [&str; 7] = [
+    "UpToDate",
+    "SWHardeningNeeded",
+    "ConfigurationNeeded",
+    "ConfigurationAndSWHardeningNeeded",
+    "OutOfDate",
+    "OutOfDateConfigurationNeeded",
+    "Revoked",
+];
+
+/// Position of a status in `TCB_STATUS_ORDER`, unknown statuses rank as the worst.
+pub fn tcb_status_rank(status: &str) -> usize {
+    TCB_STATUS_ORDER
+        .iter()
+        .position(|known| *known == status)
+        .unwrap_or(TCB_STATUS_ORDER.len())
+}
+
+pub fn is_known_tcb_status(status: &str) -> bool {
+    TCB_STATUS_ORDER.contains(&status)
+}
+
+pub fn is_tcb_degraded(old_status: &str, new_status: &str) -> bool {
+    tcb_status_rank(new_status) > tcb_status_rank(old_status)
+}
+
+/// Owner-set platform-trust requirements that `would_accept_tcb` reports on, registration does
+/// not enforce them.
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TcbPolicy {
+    /// worst accepted status in `TCB_STATUS_ORDER`
+    pub max_tcb_status: String,
+    pub banned_advisory_ids: Vec<String>,
+    pub allow_debug: bool,
+    /// `None` accepts any MRTD
+    pub allowed_mrtds: Option<Vec<String>>,
+}
+
+impl Default for TcbPolicy {
+    fn default() -> Self {
+        Self {
+            max_tcb_status: String::from("OutOfDateConfigurationNeeded"),
+            banned_advisory_ids: vec![],
+            allow_debug: false,
+            allowed_mrtds: None,
+        }
+    }
+}
+
+impl TcbPolicy {
+    /// Unknown statuses never pass, so a status dcap-qvl adds later is rejected until ranked.
+    pub fn tcb_status_ok(&self, status: &str) -> bool {
+        is_known_tcb_status(status)
+            && is_known_tcb_status(&self.max_tcb_status)
+            && tcb_status_rank(status) <= tcb_status_rank(&self.max_tcb_status)
+    }
+
+    pub fn advisories_ok(&self, advisory_ids: &[String]) -> bool {
+        !advisory_ids
+            .iter()
+            .any(|advisory_id| self.banned_advisory_ids.contains(advisory_id))
+    }
+
+    pub fn debug_ok(&self, debug: bool) -> bool {
+        self.allow_debug || !debug
+    }
+
+    pub fn mrtd_ok(&self, mrtd: &str) -> bool {
+        self.allowed_mrtds
+            .as_ref()
+            .is_none_or(|mrtds| mrtds.iter().any(|allowed| allowed == mrtd))
+    }
+}
+
+/// Bit 0 of the TD attributes is the DEBUG flag.
+pub fn is_debug_td(td_attributes: &[u8]) -> bool {
+    td_attributes[0] & 1 != 0
+}
+
+/// Platform-trust verdict for a quote, see `Contract::would_accept_tcb`. `accepted` is set only
+/// when every criterion passes.
+#[near(serializers = [json])]
+pub struct TcbDecision {
+    pub accepted: bool,
+    pub quote_verified: bool,
+    pub is_tdx: bool,
+    pub is_td10: bool,
+    pub tcb_status_ok: bool,
+    pub advisories_ok: bool,
+    pub debug_ok: bool,
+    pub mrtd_ok: bool,
+    pub error: Option<String>,
+    pub tcb_status: Option<String>,
+    pub advisory_ids: Vec<String>,
+    pub debug: bool,
+    pub mrtd: Option<String>,
+}
+
+pub fn evaluate_quote(
+    policy: &TcbPolicy,
+    quote_hex: String,
+    collateral: String,
+    now: u64,
+) -> TcbDecision {
+    let mut decision = TcbDecision {
+        accepted: false,
+        quote_verified: false,
+        is_tdx: false,
+        is_td10: false,
+        tcb_status_ok: false,
+        advisories_ok: false,
+        debug_ok: false,
+        mrtd_ok: false,
+        error: None,
+        tcb_status: None,
+        advisory_ids: vec![],
+        debug: false,
+        mrtd: None,
+    };
+
+    let quote = match decode(quote_hex) {
+        Ok(quote) => quote,
+        Err(err) => {
+            decision.error = Some(format!("quote_hex is not valid hex: {}", err));
+            return decision;
+        }
+    };
+    decision.is_tdx = detect_quote_type(&quote) == Some(QuoteType::Tdx);
+    let collateral = match collateral::parse_collateral(&collateral) {
+        Ok(collateral) => collateral,
+        Err(err) => {
+            decision.error = Some(err);
+            return decision;
+        }
+    };
+    let result = match verify::verify(&quote, &collateral, now) {
+        Ok(result) => result,
+        Err(err) => {
+            decision.error = Some(format!("report is not verified: {:?}", err));
+            return decision;
+        }
+    };
+
+    decision.quote_verified = true;
+    decision.tcb_status_ok = policy.tcb_status_ok(&result.status);
+    decision.advisories_ok = policy.advisories_ok(&result.advisory_ids);
+    decision.tcb_status = Some(result.status.clone());
+    decision.advisory_ids = result.advisory_ids.clone();
+    match result.report.as_td10() {
+        Some(report) => {
+            decision.is_td10 = true;
+            decision.debug = is_debug_td(&report.td_attributes);
+            decision.debug_ok = policy.debug_ok(decision.debug);
+            let mrtd = encode(report.mr_td);
+            decision.mrtd_ok = policy.mrtd_ok(&mrtd);
+            decision.mrtd = Some(mrtd);
+        }
+        None => decision.error = Some(String::from("report is not a TD10 report")),
+    }
+    decision.accepted = decision.quote_verified
+        && decision.is_tdx
+        && decision.is_td10
+        && decision.tcb_status_ok
+        && decision.advisories_ok
+        && decision.debug_ok
+        && decision.mrtd_ok;
+
+    decision
+}
+
+#[test]
+fn test_is_tcb_degraded() {
+    assert!(is_tcb_degraded("UpToDate", "OutOfDate"));
+    assert!(is_tcb_degraded("OutOfDate", "Revoked"));
+    assert!(is_tcb_degraded("Revoked", "SomethingNew"));
+    assert!(!is_tcb_degraded("OutOfDate", "UpToDate"));
+    assert!(!is_tcb_degraded("UpToDate", "UpToDate"));
+}
+
+#[test]
+fn test_evaluate_quote_rejects_malformed_hex() {
+    let decision = evaluate_quote(
+        &TcbPolicy::default(),
+        String::from("not hex"),
+        String::new(),
+        0,
+    );
+
+    assert!(!decision.accepted);
+    assert!(!decision.quote_verified);
+    assert!(decision.error.unwrap().starts_with("quote_hex is not valid hex"));
+}
+
+#[test]
+fn test_evaluate_quote_rejects_unverifiable_quote() {
+    let collateral = serde_json::json!({
+        "tcb_info_issuer_chain": "",
+        "tcb_info": "",
+        "tcb_info_signature": "",
+        "qe_identity_issuer_chain": "",
+        "qe_identity": "",
+        "qe_identity_signature": "",
+    });
+    let decision = evaluate_quote(
+        &TcbPolicy::default(),
+        String::from("0400ff"),
+        collateral.to_string(),
+        0,
+    );
+
+    assert!(!decision.accepted);
+    assert!(!decision.quote_verified);
+    assert!(decision.tcb_status.is_none());
+    assert!(decision.error.unwrap().starts_with("report is not verified"));
+}
+
+#[test]
+fn test_evaluate_quote_reports_malformed_collateral() {
+    let decision = evaluate_quote(
+        &TcbPolicy::default(),
+        String::from("0400ff"),
+        String::from("not json"),
+        0,
+    );
+
+    assert!(!decision.accepted);
+    assert_eq!(decision.error.unwrap(), "TCB Info should be valid JSON");
+}
+
+#[test]
+fn test_evaluate_quote_accepts_sample_quote() {
+    let (quote_hex, collateral) = collateral::sample_quote();
+    let decision = evaluate_quote(
+        &TcbPolicy::default(),
+        quote_hex,
+        collateral,
+        collateral::SAMPLE_QUOTE_TIME,
+    );
+
+    assert!(decision.error.is_none());
+    assert!(decision.quote_verified && decision.is_tdx && decision.is_td10);
+    assert!(decision.tcb_status_ok && decision.advisories_ok);
+    assert!(decision.debug_ok && decision.mrtd_ok);
+    assert!(decision.accepted);
+}
+
+#[test]
+fn test_evaluate_quote_rejects_unlisted_mrtd() {
+    let (quote_hex, collateral) = collateral::sample_quote();
+    let policy = TcbPolicy {
+        allowed_mrtds: Some(vec![encode([0u8; 48])]),
+        ..TcbPolicy::default()
+    };
+    let decision = evaluate_quote(&policy, quote_hex, collateral, collateral::SAMPLE_QUOTE_TIME);
+
+    assert!(decision.quote_verified);
+    assert!(!decision.mrtd_ok);
+    assert!(!decision.accepted);
+}
+
+#[test]
+fn test_tcb_policy_criteria() {
+    let policy = TcbPolicy {
+        max_tcb_status: String::from("SWHardeningNeeded"),
+        banned_advisory_ids: vec![String::from("INTEL-SA-00837")],
+        ..TcbPolicy::default()
+    };
+
+    assert!(policy.tcb_status_ok("UpToDate"));
+    assert!(!policy.tcb_status_ok("OutOfDate"));
+    assert!(!policy.tcb_status_ok("SomethingNew"));
+    assert!(policy.advisories_ok(&[String::from("INTEL-SA-00615")]));
+    assert!(!policy.advisories_ok(&[String::from("INTEL-SA-00837")]));
+    assert!(policy.debug_ok(false));
+    assert!(!policy.debug_ok(true));
+    assert!(policy.mrtd_ok(&encode([0u8; 48])));
+}
+
+#[test]
+fn test_unknown_max_tcb_status_rejects_everything() {
+    let policy = TcbPolicy {
+        max_tcb_status: String::from("UptoDate"),
+        ..TcbPolicy::default()
+    };
+
+    assert!(!policy.tcb_status_ok("UpToDate"));
+    assert!(!policy.tcb_status_ok("Revoked"));
+}
@@ -12,7 +12,8 @@ import {
   mutateWill,
   overwriteWill,
 } from "./tools/will_executor";
-import { formatError } from "./tools/base";
+import { agentViewBalance, formatError } from "./tools/base";
+import { planExecution, serializePlan, validateConsolidationPolicy } from "./tools/payout_plan";
 
 export function createApi(): Hono {
   const app = new Hono();
@@ -24,6 +25,7 @@ export function createApi(): Hono {
   app.post("/will", async (c) => {
     try {
       const payload = await c.req.json<WillRequestPayload>();
+      validateConsolidationPolicy(payload);
       const now = new Date().toISOString();
       overwriteWill({
         ...payload,
@@ -40,6 +42,7 @@ export function createApi(): Hono {
   app.patch("/will", async (c) => {
     try {
       const payload = await c.req.json<PartialWillPayload>();
+      validateConsolidationPolicy(payload);
       mutateWill((entry) => {
         if (payload.willText !== undefined) entry.willText = payload.willText;
         if (payload.executor !== undefined) entry.executor = payload.executor;
@@ -47,6 +50,8 @@ export function createApi(): Hono {
         if (payload.socialMediaAccounts !== undefined) entry.socialMediaAccounts = payload.socialMediaAccounts;
         if (payload.sleepSeconds !== undefined) entry.sleepSeconds = payload.sleepSeconds;
         if (payload.targetAmountYocto !== undefined) entry.targetAmountYocto = payload.targetAmountYocto;
+        if (payload.consolidationThresholdYocto !== undefined) entry.consolidationThresholdYocto = payload.consolidationThresholdYocto;
+        if (payload.consolidationTopN !== undefined) entry.consolidationTopN = payload.consolidationTopN;
       });
       return c.json(will_entry);
     } catch (error) {
@@ -54,6 +59,18 @@ export function createApi(): Hono {
     }
   });
 
+  // Payouts the executor would make right now, including whether the estate is consolidated.
+  app.get("/will/plan", async (c) => {
+    try {
+      const totalYocto = will_entry.targetAmountYocto
+        ? BigInt(will_entry.targetAmountYocto)
+        : await agentViewBalance();
+      return c.json(serializePlan(planExecution(totalYocto, will_entry)));
+    } catch (error) {
+      return c.json(formatError(error), 400);
+    }
+  });
+
   app.post("/will/beneficiaries", async (c) => {
     try {
       const payload = await c.req.json<BeneficiaryShare>();
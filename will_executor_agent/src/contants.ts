@@ -19,8 +19,12 @@ export interface WillEntry {
     socialMediaAccounts: SocialMediaAccount[];
     /// Minimum interval (seconds) between inactivity checks.
     sleepSeconds: number;
-    /// Optional fixed amount (yoctoNEAR) to distribute, otherwise vault balance is used.
+    /// Optional fixed amount (yoctoNEAR) to distribute, otherwise the distributable vault balance is used.
     targetAmountYocto?: string;
+    /// Below this total (yoctoNEAR) only the largest `consolidationTopN` beneficiaries are paid.
+    consolidationThresholdYocto?: string;
+    /// Number of beneficiaries kept when consolidating, defaults to 1.
+    consolidationTopN?: number;
     createdAtISO: string;
     updatedAtISO: string;
 }
@@ -64,6 +68,8 @@ export const will_entry: WillEntry = {
     ],
     sleepSeconds: DEFAULT_SLEEP_SECONDS,
     targetAmountYocto: undefined,
+    consolidationThresholdYocto: undefined,
+    consolidationTopN: undefined,
     createdAtISO: new Date().toISOString(),
     updatedAtISO: new Date().toISOString(),
 };
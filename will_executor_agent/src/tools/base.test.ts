@@ -0,0 +1,34 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import { executePayout } from "./base";
+import { calculatePayouts } from "./payout_plan";
+
+test("skips a share that rounds to zero without calling the contract", async () => {
+    // bob's 1% of 2 yoctoNEAR rounds down to zero, alice also receives the dust
+    const payouts = calculatePayouts(2n, [
+        { accountId: "alice.testnet", split: 99 },
+        { accountId: "bob.testnet", split: 1 },
+    ]);
+    assert.deepEqual(
+        payouts.map((payout) => payout.amountYocto),
+        [2n, 0n],
+    );
+
+    const paid: string[] = [];
+    for (const payout of payouts) {
+        await executePayout(payout, async ({ args }) => {
+            paid.push(args.account_id);
+        });
+    }
+    assert.deepEqual(paid, ["alice.testnet"]);
+});
+
+test("rejects a negative payout", async () => {
+    await assert.rejects(
+        executePayout({ accountId: "alice.testnet", amountYocto: -1n }, async () => {
+            assert.fail("pay_by_agent should not be called");
+        }),
+        /Refusing negative payout of -1 yoctoNEAR to alice.testnet/,
+    );
+});
@@ -19,23 +19,30 @@ export function parseYocto(value: unknown): bigint {
     throw new Error("Unable to parse yocto value from contract view response");
 }
     
+// The vault balance minus the storage reserve, which is all pay_by_agent will pay out.
 export async function agentViewBalance(): Promise<bigint> {
   return await agentView({
-      methodName: "get_vault_balance",
+      methodName: "get_distributable_balance",
       args: {},
   }).then(parseYocto);
 } 
 
-export async function executePayout( payout: Payout): Promise<void> {
-      if (payout.amountYocto <= 0n) {
-          console.warn(`Skipping beneficiary ${payout.accountId} due to non-positive payout.`);
-          throw new Error(`Skipping beneficiary ${payout.accountId} due to non-positive payout.`);
+type AgentCall = (request: { methodName: string; args: Record<string, string> }) => Promise<unknown>;
+
+export async function executePayout( payout: Payout, call: AgentCall = agentCall): Promise<void> {
+      // A share that rounds to zero is skipped without aborting the remaining payouts.
+      if (payout.amountYocto === 0n) {
+          console.warn(`Skipping beneficiary ${payout.accountId} due to zero payout.`);
+          return;
+      }
+      if (payout.amountYocto < 0n) {
+          throw new Error(`Refusing negative payout of ${payout.amountYocto.toString()} yoctoNEAR to ${payout.accountId}.`);
       }
 
       console.log(`Paying ${payout.accountId} ${payout.amountYocto.toString()} yoctoNEAR via pay_by_agent.`);
 
       try {
-        await agentCall({ 
+        await call({ 
             methodName: "pay_by_agent",
             args: {
                 account_id: payout.accountId,
@@ -0,0 +1,91 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import type { WillEntry } from "../contants";
+import { calculatePayouts, planExecution, validateConsolidationPolicy } from "./payout_plan";
+
+function will(overrides: Partial<WillEntry> = {}): WillEntry {
+    return {
+        willText: "",
+        executor: "executor.testnet",
+        beneficiaries: [
+            { accountId: "carol.testnet", split: 10 },
+            { accountId: "alice.testnet", split: 60 },
+            { accountId: "bob.testnet", split: 30 },
+        ],
+        socialMediaAccounts: [],
+        sleepSeconds: 60,
+        consolidationThresholdYocto: "1000",
+        consolidationTopN: 2,
+        createdAtISO: "",
+        updatedAtISO: "",
+        ...overrides,
+    };
+}
+
+function amounts(plan: ReturnType<typeof planExecution>): Record<string, bigint> {
+    return Object.fromEntries(plan.payouts.map((payout) => [payout.accountId, payout.amountYocto]));
+}
+
+test("pays every beneficiary at or above the consolidation threshold", () => {
+    for (const total of [1000n, 5000n]) {
+        const plan = planExecution(total, will());
+
+        assert.equal(plan.consolidation.applied, false);
+        assert.equal(plan.payouts.length, 3);
+        assert.equal(amounts(plan)["carol.testnet"], total / 10n);
+    }
+});
+
+test("pays only the top beneficiaries below the consolidation threshold", () => {
+    const plan = planExecution(900n, will());
+
+    assert.deepEqual(plan.consolidation, { thresholdYocto: "1000", topN: 2, applied: true });
+    // carol's share is redistributed pro rata between alice and bob
+    assert.deepEqual(amounts(plan), { "alice.testnet": 600n, "bob.testnet": 300n });
+});
+
+test("keeps only the largest beneficiary by default", () => {
+    const plan = planExecution(900n, will({ consolidationTopN: undefined }));
+
+    assert.deepEqual(amounts(plan), { "alice.testnet": 900n });
+});
+
+test("does not report consolidation when top N keeps everyone", () => {
+    const plan = planExecution(900n, will({ consolidationTopN: 3 }));
+
+    assert.equal(plan.consolidation.applied, false);
+    assert.equal(plan.payouts.length, 3);
+});
+
+test("never consolidates without a threshold", () => {
+    const plan = planExecution(1n, will({ consolidationThresholdYocto: undefined }));
+
+    assert.equal(plan.consolidation.applied, false);
+    assert.equal(plan.payouts.length, 3);
+});
+
+test("rejects invalid consolidation policies", () => {
+    for (const consolidationThresholdYocto of ["-1", "1.5", "1e24", "", 5 as unknown as string]) {
+        assert.throws(() => validateConsolidationPolicy({ consolidationThresholdYocto }));
+    }
+    for (const consolidationTopN of [0, -1, 1.5, "2" as unknown as number]) {
+        assert.throws(() => validateConsolidationPolicy({ consolidationTopN }));
+    }
+    validateConsolidationPolicy({ consolidationThresholdYocto: "1000", consolidationTopN: 2 });
+});
+
+test("pays rounding dust to the smallest account id regardless of order", () => {
+    const beneficiaries = [
+        { accountId: "carol.testnet", split: 1 },
+        { accountId: "alice.testnet", split: 1 },
+        { accountId: "bob.testnet", split: 1 },
+    ];
+
+    for (const order of [beneficiaries, [...beneficiaries].reverse()]) {
+        const payouts = Object.fromEntries(
+            calculatePayouts(100n, order).map((payout) => [payout.accountId, payout.amountYocto]),
+        );
+        assert.deepEqual(payouts, { "alice.testnet": 34n, "bob.testnet": 33n, "carol.testnet": 33n });
+    }
+});
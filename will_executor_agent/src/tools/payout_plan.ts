@@ -0,0 +1,103 @@
+import type { BeneficiaryShare, WillEntry } from "../contants";
+import type { Payout } from "./base";
+
+export type ConsolidationPolicy = Pick<WillEntry, "consolidationThresholdYocto" | "consolidationTopN">;
+
+export interface ExecutionPlan {
+    totalYocto: bigint;
+    consolidation: {
+        thresholdYocto: string | null;
+        topN: number;
+        applied: boolean;
+    };
+    payouts: Payout[];
+}
+
+// Rejects policies that would make BigInt() throw on every executor cycle.
+export function validateConsolidationPolicy(policy: ConsolidationPolicy): void {
+    const { consolidationThresholdYocto, consolidationTopN } = policy;
+    if (
+        consolidationThresholdYocto !== undefined &&
+        (typeof consolidationThresholdYocto !== "string" || !/^\d+$/.test(consolidationThresholdYocto))
+    ) {
+        throw new Error("consolidationThresholdYocto must be a non-negative integer string");
+    }
+    if (
+        consolidationTopN !== undefined &&
+        (!Number.isInteger(consolidationTopN) || consolidationTopN < 1)
+    ) {
+        throw new Error("consolidationTopN must be a positive integer");
+    }
+}
+
+// For estates below the consolidation threshold only the largest splits are paid (ties go to
+// the smaller account id). Dropped shares are redistributed pro rata among the kept
+// beneficiaries, since calculatePayouts normalises over the splits it is given.
+export function consolidateBeneficiaries(totalYocto: bigint, will: WillEntry): BeneficiaryShare[] {
+    if (!will.consolidationThresholdYocto || totalYocto >= BigInt(will.consolidationThresholdYocto)) {
+        return will.beneficiaries;
+    }
+
+    const topN = Math.max(1, will.consolidationTopN ?? 1);
+    if (topN >= will.beneficiaries.length) {
+        return will.beneficiaries;
+    }
+    return [...will.beneficiaries]
+        .sort((a, b) => b.split - a.split || (a.accountId < b.accountId ? -1 : 1))
+        .slice(0, topN);
+}
+
+export function calculatePayouts(totalYocto: bigint, beneficiaries: BeneficiaryShare[]): Payout[] {
+    const PRECISION = 10000;
+    const units = beneficiaries.map((b) => ({
+        accountId: b.accountId,
+        units: Math.round(b.split * PRECISION),
+    }));
+    const totalUnits = units.reduce((sum, item) => sum + item.units, 0);
+
+    if (totalUnits === 0) {
+        throw new Error("Total beneficiary split cannot be zero.");
+    }
+
+    const payouts = units.map(({ accountId, units: shareUnits }) => ({
+        accountId,
+        amountYocto: (totalYocto * BigInt(shareUnits)) / BigInt(totalUnits),
+    }));
+
+    // Rounding dust goes to the lexicographically smallest account id (first one on ties),
+    // so the same will always pays it to the same beneficiary regardless of insertion order.
+    const distributed = payouts.reduce((sum, payout) => sum + payout.amountYocto, 0n);
+    const dustRecipient = payouts.reduce(
+        (best, payout, i) => (payout.accountId < payouts[best].accountId ? i : best),
+        0,
+    );
+    payouts[dustRecipient].amountYocto += totalYocto - distributed;
+
+    return payouts;
+}
+
+export function planExecution(totalYocto: bigint, will: WillEntry): ExecutionPlan {
+    // consolidateBeneficiaries hands back the will's own list when it drops nobody
+    const beneficiaries = consolidateBeneficiaries(totalYocto, will);
+    return {
+        totalYocto,
+        consolidation: {
+            thresholdYocto: will.consolidationThresholdYocto ?? null,
+            topN: Math.max(1, will.consolidationTopN ?? 1),
+            applied: beneficiaries !== will.beneficiaries,
+        },
+        payouts: calculatePayouts(totalYocto, beneficiaries),
+    };
+}
+
+// BigInt amounts as strings, so the plan can be returned as JSON.
+export function serializePlan(plan: ExecutionPlan) {
+    return {
+        ...plan,
+        totalYocto: plan.totalYocto.toString(),
+        payouts: plan.payouts.map((payout) => ({
+            accountId: payout.accountId,
+            amountYocto: payout.amountYocto.toString(),
+        })),
+    };
+}
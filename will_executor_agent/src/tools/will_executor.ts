@@ -1,6 +1,7 @@
-import { BeneficiaryShare, WillEntry, will_entry } from "../contants";
+import { WillEntry, will_entry } from "../contants";
 import { checkSocialMediaActivity } from "./check_socials";
-import { agentViewBalance, executePayout, Payout } from "./base";
+import { agentViewBalance, executePayout } from "./base";
+import { planExecution } from "./payout_plan";
 
 const MS_IN_SECOND = 1000;
 // const SECONDS_PER_DAY = 24 * 60 * 60;
@@ -9,7 +10,17 @@ const SECONDS_PER_DAY = 10;
 export type WillRequestPayload = Omit<WillEntry, "createdAtISO" | "updatedAtISO">;
 
 export type PartialWillPayload = Partial<
-  Pick<WillEntry, "willText" | "executor" | "beneficiaries" | "socialMediaAccounts" | "sleepSeconds" | "targetAmountYocto">
+  Pick<
+    WillEntry,
+    | "willText"
+    | "executor"
+    | "beneficiaries"
+    | "socialMediaAccounts"
+    | "sleepSeconds"
+    | "targetAmountYocto"
+    | "consolidationThresholdYocto"
+    | "consolidationTopN"
+  >
 >;
 
 export function overwriteWill(entry: WillEntry): void {
@@ -19,6 +30,8 @@ export function overwriteWill(entry: WillEntry): void {
   will_entry.socialMediaAccounts = entry.socialMediaAccounts;
   will_entry.sleepSeconds = entry.sleepSeconds;
   will_entry.targetAmountYocto = entry.targetAmountYocto;
+  will_entry.consolidationThresholdYocto = entry.consolidationThresholdYocto;
+  will_entry.consolidationTopN = entry.consolidationTopN;
   will_entry.createdAtISO = entry.createdAtISO;
   will_entry.updatedAtISO = entry.updatedAtISO;
 }
@@ -34,35 +47,6 @@ function calculateSleepInterval(will: WillEntry): number {
     return Math.max(6, Math.min(will.sleepSeconds, socialWindowSeconds));
 }
 
-function calculatePayouts(totalYocto: bigint, beneficiaries: BeneficiaryShare[]): Payout[] {
-    const PRECISION = 10000;
-    const units = beneficiaries.map((b) => ({
-        accountId: b.accountId,
-        units: Math.round(b.split * PRECISION),
-    }));
-    const totalUnits = units.reduce((sum, item) => sum + item.units, 0);
-
-    if (totalUnits === 0) {
-        throw new Error("Total beneficiary split cannot be zero.");
-    }
-
-    const payouts = [];
-    let remaining = totalYocto;
-
-    for (let i = 0; i < units.length; i++) {
-        const { accountId, units: shareUnits } = units[i];
-        const isLast = i === units.length - 1;
-        const amount = isLast
-            ? remaining
-            : (totalYocto * BigInt(shareUnits)) / BigInt(totalUnits);
-
-        payouts.push({ accountId, amountYocto: amount });
-        remaining -= amount;
-    }
-
-    return payouts;
-}
-
 export async function willExecutorAgent(): Promise<void> {
     console.log("Will executor agent started.");
 
@@ -106,9 +90,16 @@ export async function willExecutorAgent(): Promise<void> {
             const totalYocto = will_entry.targetAmountYocto
                 ? BigInt(will_entry.targetAmountYocto)
                 : await agentViewBalance();
-            const payouts = calculatePayouts(totalYocto, will_entry.beneficiaries);
+            const plan = planExecution(totalYocto, will_entry);
+            if (plan.consolidation.applied) {
+                console.log(
+                    `Estate of ${totalYocto.toString()} yoctoNEAR is below the consolidation threshold ` +
+                    `of ${plan.consolidation.thresholdYocto}; paying ${plan.payouts.length} of ` +
+                    `${will_entry.beneficiaries.length} beneficiaries.`,
+                );
+            }
 
-            for (const payout of payouts) {
+            for (const payout of plan.payouts) {
                 await executePayout(payout);
             }
         } catch (error) {